@@ -0,0 +1,68 @@
+using System.Collections.Concurrent;
+using Discord;
+using Discord.Interactions;
+using Microsoft.Extensions.Configuration;
+using Microsoft.Extensions.DependencyInjection;
+using Red.Services;
+
+namespace Red;
+
+// Per-user, per-command cooldown of "cooldown_secs" seconds, 0 disables it. The bot owner is always exempt.
+// The cooldown only starts once the command succeeded (see Record), so a failed attempt can be retried right away.
+public class CooldownAttribute : PreconditionAttribute
+{
+    private const int DefaultSeconds = 5;
+
+    // Expired entries are swept once there are more than this many
+    private const int MaxEntries = 1000;
+
+    private static readonly ConcurrentDictionary<(ulong User, string Command), DateTimeOffset> Expiries = new();
+
+    public override async Task<PreconditionResult> CheckRequirementsAsync(IInteractionContext context,
+        ICommandInfo commandInfo, IServiceProvider services)
+    {
+        var seconds = ConfigurationHandler.GetInt(services.GetRequiredService<IConfiguration>(), "cooldown_secs",
+            DefaultSeconds);
+        if (seconds <= 0) return PreconditionResult.FromSuccess();
+
+        var key = GetKey(context.User.Id, commandInfo);
+        if (!Expiries.TryGetValue(key, out var expiry)) return PreconditionResult.FromSuccess();
+
+        var remaining = expiry - DateTimeOffset.UtcNow;
+        if (remaining <= TimeSpan.Zero)
+        {
+            Expiries.TryRemove(KeyValuePair.Create(key, expiry));
+            return PreconditionResult.FromSuccess();
+        }
+
+        // Only asked once someone is actually on cooldown, the application info is cached after the first call
+        var application = await context.Client.GetApplicationInfoAsync().ConfigureAwait(false);
+        if (context.User.Id == application.Owner.Id) return PreconditionResult.FromSuccess();
+
+        var wait = Math.Ceiling(remaining.TotalSeconds);
+        return PreconditionResult.FromError(ErrorMessage ?? $"Slow down! You can use this command again in {wait} s.");
+    }
+
+    // Called for every successful command, starts the cooldown of the ones carrying this attribute
+    internal static void Record(ICommandInfo commandInfo, IInteractionContext context, IConfiguration configuration)
+    {
+        if (!commandInfo.Preconditions.OfType<CooldownAttribute>().Any()) return;
+
+        var seconds = ConfigurationHandler.GetInt(configuration, "cooldown_secs", DefaultSeconds);
+        if (seconds <= 0) return;
+
+        var now = DateTimeOffset.UtcNow;
+        Expiries[GetKey(context.User.Id, commandInfo)] = now.AddSeconds(seconds);
+
+        if (Expiries.Count <= MaxEntries) return;
+
+        foreach (var entry in Expiries)
+            if (entry.Value <= now)
+                Expiries.TryRemove(entry);
+    }
+
+    private static (ulong, string) GetKey(ulong userId, ICommandInfo commandInfo)
+    {
+        return (userId, $"{commandInfo.Module.Name}.{commandInfo.Name}");
+    }
+}
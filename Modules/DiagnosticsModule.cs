@@ -0,0 +1,82 @@
+using System.Diagnostics;
+using Discord.Interactions;
+using Microsoft.Extensions.Configuration;
+using Red.Services;
+
+namespace Red.Modules;
+
+public class DiagnosticsModule : InteractionModuleBase<ShardedInteractionContext>
+{
+    // Discord rejects messages longer than this
+    private const int MaxMessageLength = 2000;
+
+    private readonly IConfiguration _configuration;
+    private readonly ShardHandler _shards;
+
+    public DiagnosticsModule(IConfiguration configuration, ShardHandler shards)
+    {
+        _configuration = configuration;
+        _shards = shards;
+    }
+
+    // Deferring is a real REST call to the interaction callback endpoint, so timing it gives us the API round-trip
+    [SlashCommand("apilatency", "Show local handling, REST round-trip and gateway heartbeat latency")]
+    [Cooldown]
+    public async Task ApiLatency()
+    {
+        var handling = Stopwatch.StartNew();
+
+        var rest = Stopwatch.StartNew();
+        await DeferAsync();
+        rest.Stop();
+
+        var embed = EmbedHandler.BaseEmbed()
+            .WithTitle("Latency")
+            .AddField("REST round-trip", $"{rest.ElapsedMilliseconds} ms", true)
+            .AddField("Gateway heartbeat", FormatLatency(Context.Client.GetShardFor(Context.Guild).Latency), true);
+
+        handling.Stop();
+        embed.AddField("Local handling", $"{(handling.Elapsed - rest.Elapsed).TotalMilliseconds:0.##} ms", true);
+
+        await FollowupAsync(embed: embed.Build());
+    }
+
+    [SlashCommand("shards", "List every shard with its status, latency and guild count")]
+    public async Task Shards()
+    {
+        var embed = EmbedHandler.BaseEmbed().WithTitle("Shards");
+
+        foreach (var shard in _shards.GetStatuses())
+        {
+            var status = $"{shard.State}\n{FormatLatency(shard.Latency)}\n{shard.GuildCount} guilds";
+            if (shard.ConsecutiveDisconnects > 0)
+                status += $"\n{shard.ConsecutiveDisconnects} disconnects in a row";
+
+            embed.AddField($"Shard {shard.Id}", status, true);
+        }
+
+        await RespondAsync(embed: embed.Build());
+    }
+
+    // Shows the configuration as the bot sees it after all files are merged, with secrets redacted
+    [SlashCommand("debug", "Show the effective runtime configuration")]
+    [RequireOwner]
+    public async Task DumpConfig()
+    {
+        var lines = string.Join("\n", ConfigurationHandler.Describe(_configuration));
+        if (lines.Length == 0)
+            lines = "(empty)";
+
+        var reply = $"```ini\n{lines}\n```";
+        if (reply.Length > MaxMessageLength)
+            reply = $"```ini\n{lines[..(MaxMessageLength - 20)]}\n...\n```";
+
+        await RespondAsync(reply, ephemeral: true);
+    }
+
+    // Latency stays 0 until the shard's first heartbeat is acknowledged, which would read as a perfect connection
+    private static string FormatLatency(int latency)
+    {
+        return latency > 0 ? $"{latency} ms" : "n/a";
+    }
+}
\ No newline at end of file
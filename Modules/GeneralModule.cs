@@ -0,0 +1,28 @@
+using Discord;
+using Discord.Interactions;
+
+namespace Red.Modules;
+
+public class GeneralModule : InteractionModuleBase<ShardedInteractionContext>
+{
+    // Everything the bot's commands rely on, update this together with any command that needs a new permission
+    public static readonly GuildPermissions RequiredPermissions = new(
+        viewChannel: true,
+        sendMessages: true,
+        embedLinks: true,
+        readMessageHistory: true,
+        manageMessages: true);
+
+    [SlashCommand("invite", "Get a link to add the bot to another server")]
+    public async Task Invite()
+    {
+        var application = await Context.Client.GetApplicationInfoAsync();
+        var url = "https://discord.com/api/oauth2/authorize" +
+                  $"?client_id={application.Id}&scope=bot%20applications.commands&permissions={RequiredPermissions.RawValue}";
+
+        var components = new ComponentBuilder()
+            .WithButton("Invite", style: ButtonStyle.Link, url: url);
+
+        await RespondAsync($"Add me to your server: <{url}>", components: components.Build(), ephemeral: true);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,88 @@
+using Discord;
+using Discord.Interactions;
+using Discord.Net;
+using Microsoft.Extensions.Configuration;
+using Red.Services;
+using Serilog;
+
+namespace Red.Modules;
+
+public class ModerationModule : InteractionModuleBase<ShardedInteractionContext>
+{
+    private const int DefaultCleanCount = 25;
+
+    // Discord returns at most this many messages per request, which is also the bulk-delete limit
+    private const int MaxCleanCount = 100;
+
+    // Bulk delete refuses messages older than two weeks, keep some margin for clock drift
+    private static readonly TimeSpan BulkDeleteMaxAge = TimeSpan.FromDays(14) - TimeSpan.FromMinutes(5);
+
+    private readonly IConfiguration _configuration;
+
+    public ModerationModule(IConfiguration configuration)
+    {
+        _configuration = configuration;
+    }
+
+    [SlashCommand("clean", "Delete the bot's recent messages in this channel")]
+    [RequireContext(ContextType.Guild)]
+    [RequireUserPermission(ChannelPermission.ManageMessages)]
+    [Cooldown]
+    public async Task Clean([Summary(description: "how many of the bot's messages to delete")] int? count = null)
+    {
+        var limit = Math.Clamp(count ?? ConfigurationHandler.GetInt(_configuration, "clean:default_count", DefaultCleanCount), 1,
+            MaxCleanCount);
+
+        // Without these Discord refuses to list the messages at all, tell the user what to grant instead of failing
+        var permissions = Context.Guild.CurrentUser.GetPermissions((IGuildChannel)Context.Channel);
+        var missing = new List<string>();
+        if (!permissions.ViewChannel) missing.Add("View Channel");
+        if (!permissions.ReadMessageHistory) missing.Add("Read Message History");
+
+        if (missing.Count > 0)
+        {
+            var error = EmbedHandler.ErrorEmbed(
+                $"I need the {string.Join(" and ", missing)} permission{(missing.Count == 1 ? "" : "s")} in this " +
+                "channel to find my messages.");
+            await RespondAsync(embed: error.Build(), ephemeral: true);
+            return;
+        }
+
+        await DeferAsync(ephemeral: true);
+
+        var messages = (await Context.Channel.GetMessagesAsync(MaxCleanCount).FlattenAsync())
+            .Where(x => x.Author.Id == Context.Client.CurrentUser.Id)
+            .Take(limit)
+            .ToList();
+
+        var cutoff = DateTimeOffset.UtcNow - BulkDeleteMaxAge;
+        var recent = messages.Where(x => x.Timestamp > cutoff).ToList();
+        var old = messages.Except(recent).ToList();
+        var deleted = 0;
+
+        // Bulk delete needs Manage Messages even for our own messages, without it we fall back to one by one
+        if (recent.Count >= 2 && permissions.ManageMessages)
+        {
+            await ((ITextChannel)Context.Channel).DeleteMessagesAsync(recent);
+            deleted += recent.Count;
+        }
+        else
+        {
+            old.AddRange(recent);
+        }
+
+        foreach (var message in old)
+            try
+            {
+                await message.DeleteAsync();
+                deleted++;
+            }
+            catch (HttpException ex)
+            {
+                Log.Warning(ex, "Failed to delete message {MessageId} in channel {ChannelId}", message.Id,
+                    Context.Channel.Id);
+            }
+
+        await FollowupAsync($"Removed {deleted} message{(deleted == 1 ? "" : "s")}.", ephemeral: true);
+    }
+}
\ No newline at end of file
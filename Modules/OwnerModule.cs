@@ -0,0 +1,161 @@
+using Discord;
+using Discord.Interactions;
+using Discord.Net;
+using Microsoft.Extensions.Configuration;
+using Red.Services;
+using Serilog;
+using Serilog.Events;
+
+namespace Red.Modules;
+
+public class OwnerModule : InteractionModuleBase<ShardedInteractionContext>
+{
+    // Embed field values are capped at 1024 characters
+    private const int MaxFieldLength = 1024;
+
+    private readonly CommandHandler _commandHandler;
+    private readonly IConfiguration _configuration;
+    private readonly LifetimeHandler _lifetime;
+
+    public OwnerModule(CommandHandler commandHandler, IConfiguration configuration, LifetimeHandler lifetime)
+    {
+        _commandHandler = commandHandler;
+        _configuration = configuration;
+        _lifetime = lifetime;
+    }
+
+    [SlashCommand("restart", "Disconnect and start a fresh instance of the bot")]
+    [RequireOwner]
+    public async Task Restart()
+    {
+        await RespondAsync("Restarting…", ephemeral: true);
+
+        // Runs outside the command so the interaction completes before the client goes away
+        _ = Task.Run(_lifetime.RestartAsync);
+    }
+
+    // Only affects the running process, logging:level in the configuration still applies on the next start
+    [SlashCommand("loglevel", "Show or change the minimum log level")]
+    [RequireOwner]
+    public async Task LogLevel(
+        [Choice("Verbose", "Verbose"), Choice("Debug", "Debug"), Choice("Information", "Information"),
+         Choice("Warning", "Warning"), Choice("Error", "Error"), Choice("Fatal", "Fatal")]
+        string? level = null)
+    {
+        var current = LoggingHandler.LevelSwitch.MinimumLevel;
+
+        if (level is null || !Enum.TryParse<LogEventLevel>(level, out var requested))
+        {
+            await RespondAsync($"Current log level: {current}", ephemeral: true);
+            return;
+        }
+
+        LoggingHandler.LevelSwitch.MinimumLevel = requested;
+        Log.Information("Log level changed from {Old} to {New} by {User}", current, requested, Context.User.Id);
+
+        await RespondAsync($"Log level changed from {current} to {requested}.", ephemeral: true);
+    }
+
+    [SlashCommand("reload", "Re-read the configuration files and apply what can change at runtime")]
+    [RequireOwner]
+    public async Task Reload()
+    {
+        IReadOnlyList<string> changed;
+        try
+        {
+            changed = ConfigurationHandler.Reload(_configuration);
+        }
+        catch (Exception ex)
+        {
+            Log.Warning(ex, "Reloading the configuration failed, keeping the current one");
+            await RespondAsync($"Reloading failed, the current configuration is kept: {ex.Message}", ephemeral: true);
+            return;
+        }
+
+        // Only touch the level when the file changed it, so an unrelated reload keeps a level set through loglevel
+        if (changed.Contains("logging:level", StringComparer.OrdinalIgnoreCase))
+            LoggingHandler.ApplyLevel(_configuration);
+        EmbedHandler.Initialize(_configuration);
+
+        Log.Information("Configuration reloaded by {User}, changed: {Keys}", Context.User.Id, changed);
+
+        var applied = changed.Where(x => !ConfigurationHandler.RequiresRestart(x)).ToList();
+        var restart = changed.Where(ConfigurationHandler.RequiresRestart).ToList();
+        var problems = ConfigurationHandler.Validate(_configuration);
+
+        var embed = EmbedHandler.BaseEmbed()
+            .WithTitle("Configuration reloaded")
+            .WithDescription(changed.Count == 0 ? "Nothing changed." : null)
+            .AddField("Applied", FormatKeys(applied), true)
+            .AddField("Requires restart", FormatKeys(restart), true);
+
+        if (problems.Count > 0)
+            embed.AddField("Problems", FormatKeys(problems));
+
+        await RespondAsync(embed: embed.Build(), ephemeral: true);
+    }
+
+    // Handy while developing, commands can be pushed or removed without restarting the bot
+    [SlashCommand("register", "Register or unregister the bot's commands globally or in this guild")]
+    [RequireOwner]
+    public async Task Register()
+    {
+        var inGuild = Context.Guild is not null;
+
+        var components = new ComponentBuilder()
+            .WithButton("Register globally", "commands:register:global", ButtonStyle.Primary, row: 0)
+            .WithButton("Unregister globally", "commands:unregister:global", ButtonStyle.Danger, row: 0)
+            .WithButton("Register in this guild", "commands:register:guild", ButtonStyle.Primary, disabled: !inGuild,
+                row: 1)
+            .WithButton("Unregister in this guild", "commands:unregister:guild", ButtonStyle.Danger,
+                disabled: !inGuild, row: 1);
+
+        await RespondAsync("Global changes can take up to an hour to show up, guild changes are instant.",
+            components: components.Build(), ephemeral: true);
+    }
+
+    [ComponentInteraction("commands:*:*")]
+    [RequireOwner]
+    public async Task RegisterButton(string action, string scope)
+    {
+        var guildId = scope == "guild" ? Context.Guild?.Id : null;
+        var where = guildId is not null ? "in this guild" : "globally";
+
+        if (scope == "guild" && guildId is null)
+        {
+            await RespondAsync("Guild registration only works inside a guild.", ephemeral: true);
+            return;
+        }
+
+        await DeferAsync(ephemeral: true);
+
+        try
+        {
+            if (action == "register")
+            {
+                var count = await _commandHandler.RegisterToAsync(guildId);
+                await FollowupAsync($"Registered {count} commands {where}.", ephemeral: true);
+            }
+            else
+            {
+                await _commandHandler.UnregisterFromAsync(guildId);
+                await FollowupAsync($"Removed all commands {where}.", ephemeral: true);
+            }
+
+            Log.Information("Commands {Action}ed {Where} by {User}", action, where, Context.User.Id);
+        }
+        catch (HttpException ex)
+        {
+            Log.Warning(ex, "Failed to {Action} commands {Where}", action, where);
+            await FollowupAsync($"Failed to {action} commands {where}: {ex.Reason ?? ex.Message}", ephemeral: true);
+        }
+    }
+
+    private static string FormatKeys(IReadOnlyCollection<string> keys)
+    {
+        if (keys.Count == 0) return "none";
+
+        var text = string.Join("\n", keys);
+        return text.Length <= MaxFieldLength ? text : text[..(MaxFieldLength - 1)] + "…";
+    }
+}
\ No newline at end of file
@@ -1,67 +1,188 @@
-﻿using Discord;
+﻿using System.Net;
+using Discord;
 using Discord.Interactions;
+using Discord.Net;
 using Discord.WebSocket;
 using Microsoft.Extensions.Configuration;
 using Microsoft.Extensions.DependencyInjection;
 using Red.Services;
+using Serilog;
 
 namespace Red;
 
 internal static class Program
 {
-    private static void Main()
+    private static void Main(string[] args)
     {
-        LoggingHandler.Serilog();
+        // Checking the configuration shouldn't change it, so migrations only run when the bot actually starts
+        var checkOnly = args.Contains("--check-config");
 
-        IConfiguration config = new ConfigurationBuilder()
-            .AddJsonFile("secrets.json", true)
-            .AddJsonFile("app-settings.json", true)
-            .Build();
+        IConfiguration config;
+        try
+        {
+            config = ConfigurationHandler.Build(!checkOnly);
+        }
+        catch (ConfigurationException ex)
+        {
+            // Logging isn't set up yet, it depends on the configuration we just failed to load
+            Console.Error.WriteLine($"Failed to load the configuration: {ex.Message}");
+            Environment.ExitCode = 1;
+            return;
+        }
+
+        if (checkOnly)
+        {
+            Environment.ExitCode = CheckConfig(config);
+            return;
+        }
+
+        LoggingHandler.Serilog(config);
+        EmbedHandler.Initialize(config);
+
+        if (!DataHandler.Initialize(config))
+        {
+            Environment.ExitCode = 1;
+            Log.CloseAndFlush();
+            return;
+        }
+
+        if (ConfigurationHandler.ValidateToken(config["token"]) is { } tokenError)
+        {
+            Log.Fatal("The token in {Files} is invalid: {Error}", ConfigurationHandler.Files, tokenError);
+            Environment.ExitCode = 1;
+            Log.CloseAndFlush();
+            return;
+        }
 
         RunAsync(config).GetAwaiter().GetResult();
     }
 
     private static async Task RunAsync(IConfiguration configuration)
     {
+        // Debug builds register commands to a single testing guild, without one registration can only fail
+        if (IsDebug() && (!ulong.TryParse(configuration["debug_guild_id"], out var debugGuildId) || debugGuildId == 0))
+        {
+            Log.Error("Debug builds need debug_guild_id set to the ID of the guild used for testing commands");
+            Environment.ExitCode = 1;
+            Log.CloseAndFlush();
+            return;
+        }
+
         await using var services = ConfigureServices(configuration);
 
-        var client = services.GetRequiredService<DiscordSocketClient>();
+        var client = services.GetRequiredService<DiscordShardedClient>();
         var commands = services.GetRequiredService<InteractionService>();
 
         client.Log += LoggingHandler.LogAsync;
         commands.Log += LoggingHandler.LogAsync;
 
-        // Slash Commands and Context Commands can be automatically registered, but this process needs to happen after the client enters the READY state.
+        // Slash Commands and Context Commands can be automatically registered, but this process needs to happen after a shard enters the READY state.
         // Since Global Commands take around 1 hour to register, we should use a test guild to instantly update and test our commands.
-        client.Ready += async () =>
-        {
-            if (IsDebug())
-                await commands.RegisterCommandsToGuildAsync(
-                    configuration.GetValue<ulong>("698934302720786503")); // Add here ID of testing guild.
-            else
-                await commands.RegisterCommandsGloballyAsync();
-        };
+        client.ShardReady += async _ =>
+            await services.GetRequiredService<CommandHandler>().RegisterCommandsOnReadyAsync(IsDebug());
+
+        // Registered before logging in, login retries can take a while and should still end in a clean shutdown
+        var lifetime = services.GetRequiredService<LifetimeHandler>();
+        lifetime.RegisterShutdownSignals();
+
+        services.GetRequiredService<ShardHandler>().Initialize();
+        services.GetRequiredService<GuildHandler>().Initialize();
 
         // Here we can initialize the service that will register and execute our commands
         await services.GetRequiredService<CommandHandler>().InitializeAsync();
 
-        // Bot token can be provided from the Configuration object we set up earlier
-        await client.LoginAsync(TokenType.Bot, configuration["token"]);
+        // Bot token can be provided from the Configuration object we set up earlier.
+        // Logging in also asks Discord for the recommended shard count, so a short outage would otherwise end the process.
+        try
+        {
+            await RetryHandler.RunAsync("Logging in", () => client.LoginAsync(TokenType.Bot, configuration["token"]),
+                RetryHandler.GetAttempts(configuration), lifetime.ShutdownToken);
+        }
+        catch (OperationCanceledException) when (lifetime.ShutdownToken.IsCancellationRequested)
+        {
+            await lifetime.ShutdownAsync();
+            return;
+        }
+        catch (HttpException ex) when (ex.HttpCode == HttpStatusCode.Unauthorized)
+        {
+            Log.Fatal("Discord rejected the token, copy it again from the Discord developer portal");
+            Environment.ExitCode = 1;
+            Log.CloseAndFlush();
+            return;
+        }
+        catch (Exception ex)
+        {
+            Log.Fatal(ex, "Failed to log in to Discord");
+            Environment.ExitCode = 1;
+            Log.CloseAndFlush();
+            return;
+        }
+
         await client.StartAsync();
+        Log.Information("Started {Count} shards ({Mode})", client.Shards.Count,
+            ConfigurationHandler.GetInt(configuration, "shards", 0) > 0 ? "configured" : "recommended by Discord");
+
+        services.GetRequiredService<ShardHandler>().StartLogging();
+        services.GetRequiredService<ActivityHandler>().StartRotation();
+        services.GetRequiredService<MetricsHandler>().StartSummaryLogging();
+        services.GetRequiredService<PrometheusHandler>().Start();
 
-        await Task.Delay(Timeout.Infinite);
+        await lifetime.WaitForShutdownSignalAsync();
+        await lifetime.ShutdownAsync();
     }
 
     private static ServiceProvider ConfigureServices(IConfiguration configuration)
     {
         return new ServiceCollection()
             .AddSingleton(configuration)
-            .AddSingleton<DiscordSocketClient>()
-            .AddSingleton(x => new InteractionService(x.GetRequiredService<DiscordSocketClient>()))
+            .AddSingleton(CreateClient(configuration))
+            .AddSingleton(x => new InteractionService(x.GetRequiredService<DiscordShardedClient>()))
+            .AddSingleton<MetricsHandler>()
+            .AddSingleton<PrometheusHandler>()
             .AddSingleton<CommandHandler>()
+            .AddSingleton<ShardHandler>()
+            .AddSingleton<ActivityHandler>()
+            .AddSingleton<LifetimeHandler>()
+            .AddSingleton<GuildHandler>()
             .BuildServiceProvider();
     }
 
+    // "shards" above 0 runs exactly that many shards, 0 or unset lets Discord recommend a count
+    private static DiscordShardedClient CreateClient(IConfiguration configuration)
+    {
+        var shards = ConfigurationHandler.GetInt(configuration, "shards", 0);
+        return new DiscordShardedClient(new DiscordSocketConfig { TotalShards = shards > 0 ? shards : null });
+    }
+
+    // Prints the effective configuration and its problems without starting the bot.
+    // Exits with 0 when valid, 1 when there are problems and 2 when no configuration file exists at all.
+    private static int CheckConfig(IConfiguration configuration)
+    {
+        var paths = ConfigurationHandler.Files.Select(ConfigurationHandler.GetPath).ToList();
+        if (!paths.Any(File.Exists))
+        {
+            Console.WriteLine($"No configuration file found, expected one of: {string.Join(", ", paths)}");
+            return 2;
+        }
+
+        Console.WriteLine("Effective configuration:");
+        foreach (var line in ConfigurationHandler.Describe(configuration))
+            Console.WriteLine($"  {line}");
+
+        var problems = ConfigurationHandler.Validate(configuration);
+        if (problems.Count == 0)
+        {
+            Console.WriteLine("Configuration is valid.");
+            return 0;
+        }
+
+        Console.WriteLine("Problems:");
+        foreach (var problem in problems)
+            Console.WriteLine($"  {problem}");
+
+        return 1;
+    }
+
     private static bool IsDebug()
     {
 #if DEBUG
@@ -0,0 +1,100 @@
+using Microsoft.Extensions.Configuration;
+using Red.Services;
+using Xunit;
+
+namespace Red.Tests;
+
+public class ConfigurationHandlerTests
+{
+    // "MTIzNDU2Nzg5MDEyMzQ1Njc4" is base64 for the snowflake 123456789012345678
+    private const string ValidToken = "MTIzNDU2Nzg5MDEyMzQ1Njc4.GhIjKl.abcdefghijklmnopqrstuvwxyz0123456789AB";
+
+    [Fact]
+    public void ValidateToken_AcceptsThreeSegmentsWithSnowflake()
+    {
+        Assert.Null(ConfigurationHandler.ValidateToken(ValidToken));
+    }
+
+    [Theory]
+    [InlineData(null)]
+    [InlineData("")]
+    [InlineData("placeholder_token")]
+    [InlineData("MTIzNDU2Nzg5MDEyMzQ1Njc4.GhIjKl")]
+    public void ValidateToken_RejectsWrongSegmentCount(string? token)
+    {
+        Assert.NotNull(ConfigurationHandler.ValidateToken(token));
+    }
+
+    [Fact]
+    public void ValidateToken_RejectsEmptySegment()
+    {
+        Assert.NotNull(ConfigurationHandler.ValidateToken("MTIzNDU2Nzg5MDEyMzQ1Njc4..abcdefghijklmnopqrstuvwxyz"));
+    }
+
+    [Theory]
+    [InlineData("not*base64!.GhIjKl.abcdefghijklmnopqrstuvwxyz")]
+    [InlineData("aGVsbG8gd29ybGQ.GhIjKl.abcdefghijklmnopqrstuvwxyz")] // "hello world", not a snowflake
+    public void ValidateToken_RejectsFirstSegmentWithoutSnowflake(string token)
+    {
+        Assert.NotNull(ConfigurationHandler.ValidateToken(token));
+    }
+
+    [Fact]
+    public void Validate_AcceptsMinimalConfiguration()
+    {
+        Assert.Empty(ConfigurationHandler.Validate(Build(("token", ValidToken))));
+    }
+
+    [Theory]
+    [InlineData("ten")]
+    [InlineData("2.5")]
+    [InlineData("0")]
+    public void Validate_ReportsInvalidMaxFiles(string value)
+    {
+        var problems = ConfigurationHandler.Validate(Build(("token", ValidToken), ("logging:max_files", value)));
+
+        Assert.Contains(problems, x => x.StartsWith("logging:max_files:"));
+    }
+
+    [Fact]
+    public void Validate_ReportsInvalidToken()
+    {
+        var problems = ConfigurationHandler.Validate(Build(("token", "placeholder_token")));
+
+        Assert.Contains(problems, x => x.StartsWith("token:"));
+    }
+
+    // The files live in a directory of their own, only the environment variable is shared and restored afterwards
+    [Fact]
+    public void Build_PrefersEnvironmentTokenWithoutWritingIt()
+    {
+        const string fileContent = "{ \"token\": \"placeholder_token\" }";
+        var directory = Directory.CreateDirectory(Path.Combine(Path.GetTempPath(), $"red-config-{Guid.NewGuid():N}"))
+            .FullName;
+        var path = Path.Combine(directory, "secrets.json");
+        var originalToken = Environment.GetEnvironmentVariable(ConfigurationHandler.TokenEnvironmentVariable);
+
+        File.WriteAllText(path, fileContent);
+        try
+        {
+            Environment.SetEnvironmentVariable(ConfigurationHandler.TokenEnvironmentVariable, null);
+            Assert.Equal("placeholder_token", ConfigurationHandler.Build(false, directory)["token"]);
+
+            Environment.SetEnvironmentVariable(ConfigurationHandler.TokenEnvironmentVariable, ValidToken);
+            Assert.Equal(ValidToken, ConfigurationHandler.Build(false, directory)["token"]);
+            Assert.Equal(fileContent, File.ReadAllText(path));
+        }
+        finally
+        {
+            Environment.SetEnvironmentVariable(ConfigurationHandler.TokenEnvironmentVariable, originalToken);
+            Directory.Delete(directory, true);
+        }
+    }
+
+    private static IConfiguration Build(params (string Key, string Value)[] values)
+    {
+        return new ConfigurationBuilder()
+            .AddInMemoryCollection(values.ToDictionary(x => x.Key, x => x.Value))
+            .Build();
+    }
+}
@@ -0,0 +1,102 @@
+using System.Text.Json.Nodes;
+using Red.Services;
+using Xunit;
+
+namespace Red.Tests;
+
+public class ConfigurationMigrationTests
+{
+    // A version 0 -> 1 step like the ones real renames would add
+    private static readonly Action<JsonObject>[] RenameMigrations =
+    {
+        x =>
+        {
+            if (x["idle_timeout"] is not { } value) return;
+
+            x.Remove("idle_timeout");
+            x["idle_timeout_secs"] = value;
+        }
+    };
+
+    [Fact]
+    public void Apply_MigratesVersionlessObject()
+    {
+        var root = JsonNode.Parse("{ \"token\": \"abc\", \"idle_timeout\": 300 }")!.AsObject();
+
+        Assert.True(ConfigurationMigration.Apply(root, 0, RenameMigrations));
+        Assert.Null(root["idle_timeout"]);
+        Assert.Equal(300, root["idle_timeout_secs"]!.GetValue<int>());
+        Assert.Equal(1, root["version"]!.GetValue<int>());
+    }
+
+    [Fact]
+    public void Apply_SkipsMigrationsBelowTheFileVersion()
+    {
+        var root = JsonNode.Parse("{ \"version\": 1, \"idle_timeout\": 300 }")!.AsObject();
+
+        Assert.False(ConfigurationMigration.Apply(root, 1, RenameMigrations));
+        Assert.Equal(300, root["idle_timeout"]!.GetValue<int>());
+    }
+
+    [Fact]
+    public void Migrate_RewritesVersionlessFileAndKeepsBackup()
+    {
+        const string content = "{\n  // old name\n  \"idle_timeout\": 300,\n}\n";
+        var path = Path.Combine(Path.GetTempPath(), $"red-migration-{Guid.NewGuid():N}.json");
+
+        File.WriteAllText(path, content);
+        try
+        {
+            ConfigurationMigration.Migrate(path, RenameMigrations);
+
+            var migrated = JsonNode.Parse(File.ReadAllText(path))!.AsObject();
+            Assert.Equal(300, migrated["idle_timeout_secs"]!.GetValue<int>());
+            Assert.Equal(1, migrated["version"]!.GetValue<int>());
+            Assert.Equal(content, File.ReadAllText(path + ".bak"));
+        }
+        finally
+        {
+            File.Delete(path);
+            File.Delete(path + ".bak");
+        }
+    }
+
+    // Comments don't survive a rewrite, so when no migration changes anything the file is left as it is, version
+    // included. This is a deliberate trade-off over always stamping the current version.
+    [Fact]
+    public void Migrate_DoesNotRewriteFileWhenNothingChanges()
+    {
+        const string content = "{\n  // keep me\n  \"token\": \"abc\",\n}\n";
+        var path = Path.Combine(Path.GetTempPath(), $"red-migration-{Guid.NewGuid():N}.json");
+
+        File.WriteAllText(path, content);
+        try
+        {
+            ConfigurationMigration.Migrate(path, RenameMigrations);
+
+            Assert.Equal(content, File.ReadAllText(path));
+            Assert.False(File.Exists(path + ".bak"));
+        }
+        finally
+        {
+            File.Delete(path);
+            File.Delete(path + ".bak");
+        }
+    }
+
+    [Fact]
+    public void Migrate_RejectsNonNumericVersion()
+    {
+        var path = Path.Combine(Path.GetTempPath(), $"red-migration-{Guid.NewGuid():N}.json");
+
+        File.WriteAllText(path, "{ \"version\": \"one\" }");
+        try
+        {
+            Assert.Throws<ConfigurationException>(() => ConfigurationMigration.Migrate(path));
+        }
+        finally
+        {
+            File.Delete(path);
+        }
+    }
+}
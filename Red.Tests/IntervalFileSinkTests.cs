@@ -0,0 +1,43 @@
+using Red.Services;
+using Serilog;
+using Serilog.Events;
+using Xunit;
+
+namespace Red.Tests;
+
+public class IntervalFileSinkTests
+{
+    private static readonly DateTime Noon = new(2026, 10, 15, 12, 0, 0, DateTimeKind.Local);
+
+    [Fact]
+    public void Emit_StartsNewFileEveryIntervalAndKeepsMaxFiles()
+    {
+        var directory = Directory.CreateDirectory(Path.Combine(Path.GetTempPath(), $"red-logs-{Guid.NewGuid():N}"))
+            .FullName;
+
+        try
+        {
+            using (var sink = new IntervalFileSink(directory, "log", TimeSpan.FromMinutes(30), 2, null,
+                       path => new LoggerConfiguration().WriteTo.File(path).CreateLogger()))
+            {
+                sink.Emit(CreateEvent(Noon));
+                sink.Emit(CreateEvent(Noon.AddMinutes(10)));
+                sink.Emit(CreateEvent(Noon.AddMinutes(30)));
+                sink.Emit(CreateEvent(Noon.AddMinutes(75)));
+            }
+
+            var files = Directory.GetFiles(directory).Select(x => Path.GetFileName(x)).OrderBy(x => x).ToList();
+            Assert.Equal(new[] { "log202610151230.txt", "log202610151300.txt" }, files);
+        }
+        finally
+        {
+            Directory.Delete(directory, true);
+        }
+    }
+
+    private static LogEvent CreateEvent(DateTime timestamp)
+    {
+        return new LogEvent(new DateTimeOffset(timestamp), LogEventLevel.Information, null, MessageTemplate.Empty,
+            Array.Empty<LogEventProperty>());
+    }
+}
@@ -0,0 +1,58 @@
+using Red.Services;
+using Serilog;
+using Xunit;
+
+namespace Red.Tests;
+
+public class LoggingHandlerTests
+{
+    [Theory]
+    [InlineData("1d", 24 * 60)]
+    [InlineData("d", 24 * 60)]
+    [InlineData("2d", 2 * 24 * 60)]
+    [InlineData("1h", 60)]
+    [InlineData("12h", 12 * 60)]
+    [InlineData(" 1M ", 1)]
+    [InlineData("30m", 30)]
+    public void TryParseRotation_HonoursCountAndUnit(string value, int minutes)
+    {
+        Assert.True(LoggingHandler.TryParseRotation(value, out var rotation));
+        Assert.Equal(TimeSpan.FromMinutes(minutes), rotation);
+    }
+
+    [Theory]
+    [InlineData("7")]
+    [InlineData("")]
+    [InlineData("0h")]
+    [InlineData("-1h")]
+    [InlineData("1.5h")]
+    [InlineData("12w")]
+    [InlineData("99999999d")]
+    public void TryParseRotation_RejectsEverythingElse(string value)
+    {
+        Assert.False(LoggingHandler.TryParseRotation(value, out _));
+    }
+
+    [Theory]
+    [InlineData("1d", RollingInterval.Day)]
+    [InlineData("1h", RollingInterval.Hour)]
+    [InlineData("1m", RollingInterval.Minute)]
+    public void ToRollingInterval_UsesSerilogForSingleUnits(string value, RollingInterval expected)
+    {
+        LoggingHandler.TryParseRotation(value, out var rotation);
+
+        Assert.Equal(expected, LoggingHandler.ToRollingInterval(rotation));
+    }
+
+    [Theory]
+    [InlineData("2d")]
+    [InlineData("12h")]
+    [InlineData("30m")]
+    [InlineData("90m")]
+    public void ToRollingInterval_LeavesOtherIntervalsToIntervalFileSink(string value)
+    {
+        LoggingHandler.TryParseRotation(value, out var rotation);
+
+        Assert.Null(LoggingHandler.ToRollingInterval(rotation));
+    }
+}
@@ -0,0 +1,61 @@
+using Discord;
+using Discord.WebSocket;
+using Microsoft.Extensions.Configuration;
+using Serilog;
+
+namespace Red.Services;
+
+public class ActivityHandler
+{
+    private const int DefaultIntervalSeconds = 30;
+
+    // Presence updates are heavily rate limited, rotating faster than this is asking for trouble
+    private const int MinIntervalSeconds = 15;
+
+    private readonly DiscordShardedClient _client;
+    private readonly IConfiguration _configuration;
+
+    public ActivityHandler(DiscordShardedClient client, IConfiguration configuration)
+    {
+        _client = client;
+        _configuration = configuration;
+    }
+
+    // Cycles through "activity:messages" every "activity:interval_secs" seconds. {guilds} is replaced by the guild count.
+    public void StartRotation()
+    {
+        var messages = _configuration.GetSection("activity:messages").Get<string[]>() ?? Array.Empty<string>();
+        if (messages.Length == 0) return;
+
+        var seconds = ConfigurationHandler.GetInt(_configuration, "activity:interval_secs", DefaultIntervalSeconds);
+        if (seconds < MinIntervalSeconds)
+        {
+            Log.Warning("activity:interval_secs must be at least {Min}, got {Value}. Using {Min} instead",
+                MinIntervalSeconds, seconds, MinIntervalSeconds);
+            seconds = MinIntervalSeconds;
+        }
+
+        _ = Task.Run(async () =>
+        {
+            using var timer = new PeriodicTimer(TimeSpan.FromSeconds(seconds));
+            var index = 0;
+
+            do
+            {
+                if (_client.Shards.All(x => x.ConnectionState != ConnectionState.Connected)) continue;
+
+                var message = messages[index++ % messages.Length]
+                    .Replace("{guilds}", _client.Guilds.Count.ToString());
+
+                try
+                {
+                    await _client.SetGameAsync(message);
+                }
+                catch (Exception ex)
+                {
+                    Log.Warning(ex, "Failed to update the activity to {Message}", message);
+                }
+            } while (await timer.WaitForNextTickAsync());
+        });
+    }
+}
\ No newline at end of file
@@ -0,0 +1,74 @@
+using System.IO.Compression;
+using Serilog.Debugging;
+using Serilog.Sinks.File;
+
+namespace Red.Services;
+
+// Gzips log files into "<name>.gz" right before the retention policy deletes them. The sink's retention doesn't know
+// about the archives, so they are capped at the same "max_files" here, oldest first.
+internal class ArchiveHooks : FileLifecycleHooks
+{
+    private readonly int _maxArchives;
+
+    public ArchiveHooks(int maxArchives)
+    {
+        _maxArchives = maxArchives;
+    }
+
+    public override void OnFileDeleting(string path)
+    {
+        var archivePath = path + ".gz";
+
+        try
+        {
+            using var source = File.OpenRead(path);
+            using var archive = File.Create(archivePath);
+            using var gzip = new GZipStream(archive, CompressionLevel.Optimal);
+            source.CopyTo(gzip);
+        }
+        catch (Exception ex)
+        {
+            // We are inside the file sink here, so errors go to SelfLog instead of the logger that called us.
+            // Rethrowing makes the sink skip the deletion and the original file stays around.
+            SelfLog.WriteLine("Failed to archive {0}, keeping the original: {1}", path, ex);
+            TryDelete(archivePath);
+            throw;
+        }
+
+        PruneArchives(path);
+    }
+
+    // "log20261015_001.txt" and "log20261015.txt" both belong to "log", the audit trail's files to "commands"
+    private void PruneArchives(string path)
+    {
+        var directory = Path.GetDirectoryName(Path.GetFullPath(path))!;
+        var prefix = Path.GetFileNameWithoutExtension(path).TrimEnd("_0123456789".ToCharArray());
+
+        try
+        {
+            var expired = Directory.GetFiles(directory, $"{prefix}*{Path.GetExtension(path)}.gz")
+                .OrderByDescending(x => x, StringComparer.Ordinal)
+                .Skip(_maxArchives);
+
+            foreach (var archive in expired)
+                File.Delete(archive);
+        }
+        catch (Exception ex) when (ex is IOException or UnauthorizedAccessException)
+        {
+            // Whatever is left over gets another chance the next time a file is archived
+            SelfLog.WriteLine("Failed to remove old log archives in {0}: {1}", directory, ex);
+        }
+    }
+
+    private static void TryDelete(string path)
+    {
+        try
+        {
+            File.Delete(path);
+        }
+        catch (IOException)
+        {
+            // Nothing more we can do, the partial archive will be overwritten on the next attempt
+        }
+    }
+}
\ No newline at end of file
@@ -1,20 +1,35 @@
+using System.Diagnostics;
 using System.Reflection;
+using System.Security.Cryptography;
+using System.Text;
 using Discord;
 using Discord.Interactions;
 using Discord.WebSocket;
+using Microsoft.Extensions.Configuration;
+using Serilog;
+using static System.FormattableString;
 
 namespace Red.Services;
 
 public class CommandHandler
 {
-    private readonly DiscordSocketClient _client;
+    // Holds the hash of the command definitions that were last pushed to Discord
+    private static string RegistrationStatePath => DataHandler.GetPath("registration.hash");
+
+    private readonly DiscordShardedClient _client;
     private readonly InteractionService _commands;
+    private readonly IConfiguration _configuration;
+    private readonly MetricsHandler _metrics;
     private readonly IServiceProvider _services;
+    private int _registeredOnReady;
 
-    public CommandHandler(DiscordSocketClient client, InteractionService commands, IServiceProvider services)
+    public CommandHandler(DiscordShardedClient client, InteractionService commands, IConfiguration configuration,
+        MetricsHandler metrics, IServiceProvider services)
     {
         _client = client;
         _commands = commands;
+        _configuration = configuration;
+        _metrics = metrics;
         _services = services;
     }
 
@@ -32,105 +47,288 @@ public class CommandHandler
         _commands.ComponentCommandExecuted += ComponentCommandExecuted;
     }
 
-    private static Task ComponentCommandExecuted(ComponentCommandInfo arg1, IInteractionContext arg2, IResult arg3)
+    // ShardReady fires for every shard and again whenever one has to start a new session, registering once per
+    // process is enough. Runs in the background so retries don't hold up the gateway.
+    public Task RegisterCommandsOnReadyAsync(bool debug)
     {
-        if (arg3.IsSuccess) return Task.CompletedTask;
-        switch (arg3.Error)
+        if (Interlocked.Exchange(ref _registeredOnReady, 1) == 1)
         {
-            case InteractionCommandError.UnmetPrecondition:
-                // implement
-                break;
-            case InteractionCommandError.UnknownCommand:
-                // implement
-                break;
-            case InteractionCommandError.BadArgs:
-                // implement
-                break;
-            case InteractionCommandError.Exception:
-                // implement
-                break;
-            case InteractionCommandError.Unsuccessful:
-                // implement
-                break;
-            case InteractionCommandError.ConvertFailed:
-                break;
-            case InteractionCommandError.ParseFailed:
-                break;
-            case null:
-                break;
+            Log.Debug("Ready fired again, commands were already registered");
+            return Task.CompletedTask;
         }
 
+        _ = Task.Run(async () =>
+        {
+            try
+            {
+                await RetryHandler.RunAsync("Registering commands", () => RegisterCommandsAsync(debug),
+                    RetryHandler.GetAttempts(_configuration));
+            }
+            catch (Exception ex)
+            {
+                // The commands registered last time keep working, so this isn't worth taking the bot down for
+                Log.Error(ex, "Failed to register commands, Discord keeps serving the previously registered ones");
+            }
+        });
+
         return Task.CompletedTask;
     }
 
-    private static Task ContextCommandExecuted(ContextCommandInfo arg1, IInteractionContext arg2, IResult arg3)
+    public async Task RegisterCommandsAsync(bool debug)
     {
-        if (arg3.IsSuccess) return Task.CompletedTask;
-        switch (arg3.Error)
+        var targets = debug
+            ? new ulong?[] { _configuration.GetValue<ulong>("debug_guild_id") }
+            : GetRegistrationTargets();
+        var hash = ComputeCommandsHash(targets.Select(x => x is null ? "global" : $"guild:{x}"));
+
+        if (ConfigurationHandler.GetBool(_configuration, "force_register"))
+            Log.Information("force_register is set, registering commands unconditionally");
+        else if (await IsRegistrationCurrentAsync(targets, hash))
         {
-            case InteractionCommandError.UnmetPrecondition:
-                // implement
-                break;
-            case InteractionCommandError.UnknownCommand:
-                // implement
-                break;
-            case InteractionCommandError.BadArgs:
-                // implement
-                break;
-            case InteractionCommandError.Exception:
-                // implement
-                break;
-            case InteractionCommandError.Unsuccessful:
-                // implement
-                break;
-            case InteractionCommandError.ConvertFailed:
-                break;
-            case InteractionCommandError.ParseFailed:
-                break;
-            case null:
-                break;
+            Log.Information("Commands unchanged since last registration, skipping");
+            return;
         }
 
-        return Task.CompletedTask;
+        // Both calls use Discord's bulk overwrite endpoint, so registration costs a single API request per target
+        // regardless of how many commands we have and stale commands are removed in the same call.
+        var stopwatch = Stopwatch.StartNew();
+
+        foreach (var guildId in targets)
+        {
+            IReadOnlyCollection<IApplicationCommand> registered;
+
+            if (guildId is not null)
+            {
+                Log.Information("Registering commands to guild {GuildId}...", guildId);
+                registered = await _commands.RegisterCommandsToGuildAsync(guildId.Value);
+            }
+            else
+            {
+                Log.Information("Registering commands globally...");
+                registered = await _commands.RegisterCommandsGloballyAsync();
+            }
+
+            Log.Information("Registered {Count} commands", registered.Count);
+        }
+
+        stopwatch.Stop();
+        Log.Information("Command registration took {Elapsed} ms", stopwatch.ElapsedMilliseconds);
+
+        await File.WriteAllTextAsync(RegistrationStatePath, hash);
+    }
+
+    // Runtime counterpart of RegisterCommandsAsync used by the register command, null targets global commands.
+    // Discord no longer matches the stored hash afterwards, so it's dropped and the next start checks again.
+    public async Task<int> RegisterToAsync(ulong? guildId)
+    {
+        IReadOnlyCollection<IApplicationCommand> registered;
+        if (guildId is not null)
+            registered = await _commands.RegisterCommandsToGuildAsync(guildId.Value);
+        else
+            registered = await _commands.RegisterCommandsGloballyAsync();
+
+        File.Delete(RegistrationStatePath);
+        return registered.Count;
+    }
+
+    public async Task UnregisterFromAsync(ulong? guildId)
+    {
+        if (guildId is not null)
+            await _client.Rest.BulkOverwriteGuildCommands(Array.Empty<ApplicationCommandProperties>(), guildId.Value);
+        else
+            await _client.Rest.BulkOverwriteGlobalCommands(Array.Empty<ApplicationCommandProperties>());
+
+        File.Delete(RegistrationStatePath);
     }
 
-    private static Task SlashCommandExecuted(SlashCommandInfo arg1, IInteractionContext arg2, IResult arg3)
+    // Resolves "registration:mode" and "registration:guild_ids" into a list of targets, null standing for global
+    private IReadOnlyList<ulong?> GetRegistrationTargets()
     {
-        if (arg3.IsSuccess) return Task.CompletedTask;
-        switch (arg3.Error)
+        var section = _configuration.GetSection("registration");
+
+        var modeValue = section["mode"];
+        var mode = RegistrationMode.Global;
+        if (modeValue is not null && !Enum.TryParse(modeValue, true, out mode))
         {
-            case InteractionCommandError.UnmetPrecondition:
-                // implement
-                break;
-            case InteractionCommandError.UnknownCommand:
-                // implement
-                break;
-            case InteractionCommandError.BadArgs:
-                // implement
-                break;
-            case InteractionCommandError.Exception:
-                // implement
-                break;
-            case InteractionCommandError.Unsuccessful:
-                // implement
-                break;
-            case InteractionCommandError.ConvertFailed:
-                break;
-            case InteractionCommandError.ParseFailed:
-                break;
-            case null:
-                break;
+            Log.Warning("registration:mode must be one of global, guilds or both, got {Value}. Using global",
+                modeValue);
+            mode = RegistrationMode.Global;
         }
 
-        return Task.CompletedTask;
+        var guildIds = new List<ulong?>();
+        foreach (var value in section.GetSection("guild_ids").GetChildren().Select(x => x.Value))
+            if (ulong.TryParse(value, out var guildId))
+                guildIds.Add(guildId);
+            else
+                Log.Warning("Ignoring registration:guild_ids entry {Value}, it is not a guild ID", value);
+
+        if (mode != RegistrationMode.Global && guildIds.Count == 0)
+        {
+            Log.Warning("registration:mode is {Mode} but registration:guild_ids is empty. Registering globally", mode);
+            mode = RegistrationMode.Global;
+        }
+
+        return mode switch
+        {
+            RegistrationMode.Guilds => guildIds,
+            RegistrationMode.Both => guildIds.Prepend(null).ToList(),
+            _ => new ulong?[] { null }
+        };
+    }
+
+    private async Task<bool> IsRegistrationCurrentAsync(IEnumerable<ulong?> targets, string hash)
+    {
+        if (!File.Exists(RegistrationStatePath) || await File.ReadAllTextAsync(RegistrationStatePath) != hash)
+            return false;
+
+        if (_commands.SlashCommands.Count == 0 && _commands.ContextCommands.Count == 0)
+            return true;
+
+        // The hash only tells us what we pushed last time, make sure the commands are still there on Discord's side
+        foreach (var guildId in targets)
+        {
+            IReadOnlyCollection<IApplicationCommand> existing;
+            if (guildId is not null)
+                existing = await _client.Rest.GetGuildApplicationCommands(guildId.Value);
+            else
+                existing = await _client.Rest.GetGlobalApplicationCommands();
+
+            if (existing.Count == 0)
+                return false;
+        }
+
+        return true;
+    }
+
+    private string ComputeCommandsHash(IEnumerable<string> targets)
+    {
+        var definitions = targets.ToList();
+
+        // Everything Discord stores about a command has to be in here, otherwise changing only that is never pushed
+        foreach (var command in _commands.SlashCommands)
+        {
+            var parameters = command.Parameters.Select(x => string.Join(":", x.Name, x.Description,
+                x.ParameterType.FullName, x.IsRequired, x.IsAutocomplete, Invariant($"{x.MinValue}"),
+                Invariant($"{x.MaxValue}"), string.Join(",", x.ChannelTypes),
+                string.Join(",", x.Choices.Select(c => Invariant($"{c.Name}={c.Value}")))));
+
+            definitions.Add($"slash|{GroupPath(command.Module)}|{command.Name}|{command.Description}|" +
+                            $"{command.DefaultPermission}|{string.Join(";", parameters)}");
+        }
+
+        definitions.AddRange(_commands.ContextCommands.Select(x =>
+            $"context|{x.CommandType}|{x.Name}|{x.DefaultPermission}"));
+        definitions.Sort(StringComparer.Ordinal);
+
+        var bytes = SHA256.HashData(Encoding.UTF8.GetBytes(string.Join("\n", definitions)));
+        return Convert.ToHexString(bytes);
+    }
+
+    private static string GroupPath(ModuleInfo module)
+    {
+        var groups = new List<string>();
+        for (var current = module; current is not null; current = current.Parent)
+            if (current.IsSlashGroup)
+                groups.Insert(0, current.SlashGroupName);
+
+        return string.Join(" ", groups);
+    }
+
+    // Discord.Net passes a null command when the name or custom ID is unknown, the handlers below then fall back to
+    // what the user sent so the failure can still be logged and reported
+    private async Task ComponentCommandExecuted(ComponentCommandInfo? arg1, IInteractionContext arg2, IResult arg3)
+    {
+        var name = arg1?.Name ?? GetUnknownName(arg2.Interaction);
+        LoggingHandler.LogCommand(name, arg2, arg3);
+        _metrics.CommandExecuted(name, arg2.Interaction.Id, arg3.IsSuccess);
+
+        if (!arg3.IsSuccess)
+            await ReportErrorAsync(name, arg2, arg3);
+        else if (arg1 is not null)
+            CooldownAttribute.Record(arg1, arg2, _configuration);
+    }
+
+    private async Task ContextCommandExecuted(ContextCommandInfo? arg1, IInteractionContext arg2, IResult arg3)
+    {
+        var name = arg1?.Name ?? GetUnknownName(arg2.Interaction);
+        LoggingHandler.LogCommand(name, arg2, arg3);
+        _metrics.CommandExecuted(name, arg2.Interaction.Id, arg3.IsSuccess);
+
+        if (!arg3.IsSuccess)
+            await ReportErrorAsync(name, arg2, arg3);
+        else if (arg1 is not null)
+            CooldownAttribute.Record(arg1, arg2, _configuration);
+    }
+
+    private async Task SlashCommandExecuted(SlashCommandInfo? arg1, IInteractionContext arg2, IResult arg3)
+    {
+        var name = arg1 is not null
+            ? $"{GroupPath(arg1.Module)} {arg1.Name}".Trim()
+            : GetUnknownName(arg2.Interaction);
+        LoggingHandler.LogCommand(name, arg2, arg3);
+        _metrics.CommandExecuted(name, arg2.Interaction.Id, arg3.IsSuccess);
+
+        if (!arg3.IsSuccess)
+            await ReportErrorAsync(name, arg2, arg3);
+        else if (arg1 is not null)
+            CooldownAttribute.Record(arg1, arg2, _configuration);
+    }
+
+    private static string GetUnknownName(IDiscordInteraction interaction)
+    {
+        return interaction switch
+        {
+            ISlashCommandInteraction slash => slash.Data.Name,
+            IUserCommandInteraction user => user.Data.Name,
+            IMessageCommandInteraction message => message.Data.Name,
+            IComponentInteraction component => component.Data.CustomId,
+            _ => "unknown"
+        };
+    }
+
+    // Every failed command ends up here, so users get the same ephemeral error embed whatever went wrong
+    private static async Task ReportErrorAsync(string name, IInteractionContext context, IResult result)
+    {
+        if (result is ExecuteResult { Exception: { } exception })
+            Log.Error(exception, "Command {Command} by {User} threw an exception", name, context.User.Id);
+        else
+            Log.Warning("Command {Command} by {User} failed: {Error} {Reason}", name, context.User.Id, result.Error,
+                result.ErrorReason);
+
+        // Exception messages are meant for us, everything else is about what the user did
+        var message = result.Error switch
+        {
+            InteractionCommandError.UnknownCommand => "This command doesn't exist anymore, it may have been removed.",
+            InteractionCommandError.BadArgs or InteractionCommandError.ConvertFailed or
+                InteractionCommandError.ParseFailed => $"Invalid arguments: {result.ErrorReason}",
+            InteractionCommandError.Exception => "Something went wrong while running this command.",
+            _ => result.ErrorReason
+        };
+
+        var embed = EmbedHandler.ErrorEmbed(message).Build();
+
+        try
+        {
+            if (context.Interaction.HasResponded)
+                await context.Interaction.FollowupAsync(embed: embed, ephemeral: true);
+            else
+                await context.Interaction.RespondAsync(embed: embed, ephemeral: true);
+        }
+        catch (Exception ex)
+        {
+            // Interactions expire after 15 minutes, there is nobody left to tell
+            Log.Warning(ex, "Failed to report the error of command {Command} to {User}", name, context.User.Id);
+        }
     }
 
     private async Task HandleInteraction(SocketInteraction arg)
     {
         try
         {
+            _metrics.InteractionReceived(arg.Id);
+
             // Create an execution context that matches the generic type parameter of your InteractionModuleBase<T> modules
-            var ctx = new SocketInteractionContext(_client, arg);
+            var ctx = new ShardedInteractionContext(_client, arg);
             await _commands.ExecuteCommandAsync(ctx, _services);
         }
         catch (Exception ex)
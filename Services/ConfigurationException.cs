@@ -0,0 +1,13 @@
+namespace Red.Services;
+
+// Raised when a configuration file exists but can't be used, the message tells the operator how to fix it
+public class ConfigurationException : Exception
+{
+    public ConfigurationException(string path, string message, Exception? innerException = null)
+        : base($"{path}: {message}", innerException)
+    {
+        Path = path;
+    }
+
+    public string Path { get; }
+}
\ No newline at end of file
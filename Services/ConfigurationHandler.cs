@@ -0,0 +1,285 @@
+using System.Globalization;
+using System.Text;
+using System.Text.Json;
+using Microsoft.Extensions.Configuration;
+using Serilog;
+using Serilog.Events;
+
+namespace Red.Services;
+
+internal static class ConfigurationHandler
+{
+    // Later files override keys of earlier ones, sections are merged key by key.
+    // app-settings.local.json is meant for per-deployment overrides and is not tracked by git.
+    internal static readonly string[] Files = { "secrets.json", "app-settings.json", "app-settings.local.json" };
+
+    private const string Redacted = "[REDACTED]";
+
+    // Any key containing one of these is treated as a secret and never printed
+    private static readonly string[] SecretKeyParts = { "token", "secret", "password" };
+
+    private static readonly string[] BooleanKeys =
+    {
+        "force_register", "logging:console", "logging:force_color", "logging:audit_commands", "logging:compress",
+        "welcome:enabled", "metrics:prometheus"
+    };
+
+    private static readonly string[] IntegerKeys =
+    {
+        "logging:shard_status_interval_secs", "reconnect_alert_threshold", "activity:interval_secs",
+        "clean:default_count", "metrics:summary_interval_mins", "shards", "cooldown_secs", "metrics:port",
+        "startup_retries", "version"
+    };
+
+    // Only read at startup, logging:level is the exception as it's applied through LoggingHandler.ApplyLevel.
+    // Registration settings are only used by the registration on the first ready.
+    private static readonly string[] RestartKeys =
+    {
+        "token", "shards", "data_dir", "debug_guild_id", "logging", "activity", "metrics", "registration",
+        "force_register", "startup_retries"
+    };
+
+    // Lets containers pass the token without writing it into any of the files
+    internal const string TokenEnvironmentVariable = "RED_DISCORD_TOKEN";
+
+    // Precedence from lowest to highest: secrets.json, app-settings.json, app-settings.local.json, RED_DISCORD_TOKEN.
+    // Missing files are skipped, files that exist but can't be read throw a ConfigurationException.
+    // Files are only written back by migrate, and only with their own content, so the environment token never ends up
+    // on disk. baseDirectory replaces the executable's directory, tests point it at a directory of their own.
+    internal static IConfiguration Build(bool migrate = true, string? baseDirectory = null)
+    {
+        var builder = new ConfigurationBuilder();
+        foreach (var file in Files)
+        {
+            var path = GetPath(file, baseDirectory ?? AppContext.BaseDirectory);
+            EnsureReadable(path);
+            if (migrate)
+                ConfigurationMigration.Migrate(path);
+
+            builder.AddJsonFile(path, true);
+        }
+
+        var token = Environment.GetEnvironmentVariable(TokenEnvironmentVariable);
+        if (!string.IsNullOrEmpty(token))
+            builder.AddInMemoryCollection(new Dictionary<string, string> { ["token"] = token });
+
+        return builder.Build();
+    }
+
+    // The build copies the files next to the executable, so that's where they are looked up whatever the working
+    // directory is. Validation, migration and loading all have to go through this to see the same file.
+    internal static string GetPath(string file)
+    {
+        return GetPath(file, AppContext.BaseDirectory);
+    }
+
+    private static string GetPath(string file, string baseDirectory)
+    {
+        return Path.Combine(baseDirectory, file);
+    }
+
+    // Re-reads every file in place, so services holding the configuration see the new values right away.
+    // The files are parsed into a throwaway configuration first, a broken file throws without touching the live one.
+    // Returns the keys whose value changed.
+    internal static IReadOnlyList<string> Reload(IConfiguration configuration)
+    {
+        _ = Build(false);
+
+        var before = Snapshot(configuration);
+        ((IConfigurationRoot)configuration).Reload();
+        var after = Snapshot(configuration);
+
+        return before.Keys.Union(after.Keys, StringComparer.OrdinalIgnoreCase)
+            .Where(x => before.GetValueOrDefault(x) != after.GetValueOrDefault(x))
+            .OrderBy(x => x, StringComparer.OrdinalIgnoreCase)
+            .ToList();
+    }
+
+    internal static bool RequiresRestart(string key)
+    {
+        return !key.Equals("logging:level", StringComparison.OrdinalIgnoreCase) && RestartKeys.Any(x =>
+            key.Equals(x, StringComparison.OrdinalIgnoreCase) ||
+            key.StartsWith(x + ":", StringComparison.OrdinalIgnoreCase));
+    }
+
+    private static Dictionary<string, string> Snapshot(IConfiguration configuration)
+    {
+        return configuration.AsEnumerable()
+            .Where(x => x.Value is not null)
+            .ToDictionary(x => x.Key, x => x.Value, StringComparer.OrdinalIgnoreCase);
+    }
+
+    // The JSON provider silently skips a directory and fails with an opaque error on permission problems or broken
+    // JSON, which --check-config would otherwise report as a crash
+    private static void EnsureReadable(string path)
+    {
+        if (Directory.Exists(path))
+            throw new ConfigurationException(path, "is a directory, it must be a JSON file. Remove or rename it.");
+
+        if (!File.Exists(path)) return;
+
+        try
+        {
+            using var stream = File.OpenRead(path);
+
+            // Same leniency as the JSON configuration provider
+            using var document = JsonDocument.Parse(stream, new JsonDocumentOptions
+            {
+                CommentHandling = JsonCommentHandling.Skip,
+                AllowTrailingCommas = true
+            });
+
+            if (document.RootElement.ValueKind != JsonValueKind.Object)
+                throw new ConfigurationException(path, "must contain a JSON object.");
+        }
+        catch (JsonException ex)
+        {
+            throw new ConfigurationException(path, $"is not valid JSON: {ex.Message}", ex);
+        }
+        catch (UnauthorizedAccessException ex)
+        {
+            throw new ConfigurationException(path,
+                $"permission denied. Make sure the user running the bot ({Environment.UserName}) can read it.", ex);
+        }
+        catch (IOException ex)
+        {
+            throw new ConfigurationException(path, $"can't be read: {ex.Message}", ex);
+        }
+    }
+
+    internal static bool IsSecret(string key)
+    {
+        return SecretKeyParts.Any(x => key.Contains(x, StringComparison.OrdinalIgnoreCase));
+    }
+
+    // Flattens the effective configuration into "key = value" lines with secrets redacted
+    internal static IEnumerable<string> Describe(IConfiguration configuration)
+    {
+        return configuration.AsEnumerable()
+            .Where(x => x.Value is not null)
+            .OrderBy(x => x.Key, StringComparer.OrdinalIgnoreCase)
+            .Select(x => $"{x.Key} = {(IsSecret(x.Key) ? Redacted : x.Value)}");
+    }
+
+    // Bot tokens are three dot-separated segments, the first one being the base64 encoded bot user ID.
+    // Returns why the token can't be valid, or null when it looks fine.
+    internal static string? ValidateToken(string? token)
+    {
+        if (string.IsNullOrWhiteSpace(token))
+            return "missing, the bot can't log in without it";
+
+        var segments = token.Split('.');
+        if (segments.Length != 3 || segments.Any(string.IsNullOrEmpty))
+            return "must consist of three dot-separated segments, copy it again from the Discord developer portal";
+
+        var id = segments[0].Replace('-', '+').Replace('_', '/');
+        id = id.PadRight(id.Length + (4 - id.Length % 4) % 4, '=');
+
+        try
+        {
+            var decoded = Encoding.UTF8.GetString(Convert.FromBase64String(id));
+            if (ulong.TryParse(decoded, NumberStyles.None, CultureInfo.InvariantCulture, out _))
+                return null;
+        }
+        catch (FormatException)
+        {
+            // Not base64 at all, reported below
+        }
+
+        return "the first segment doesn't encode a bot user ID, copy it again from the Discord developer portal";
+    }
+
+    // Like GetValue, but a value that doesn't parse is logged and replaced by the default instead of throwing
+    internal static int GetInt(IConfiguration configuration, string key, int defaultValue)
+    {
+        var value = configuration[key];
+        if (value is null) return defaultValue;
+        if (int.TryParse(value, out var result)) return result;
+
+        Log.Warning("{Key} must be a whole number, got {Value}. Using {Default} instead", key, value, defaultValue);
+        return defaultValue;
+    }
+
+    internal static bool GetBool(IConfiguration configuration, string key, bool defaultValue = false)
+    {
+        var value = configuration[key];
+        if (value is null) return defaultValue;
+        if (bool.TryParse(value, out var result)) return result;
+
+        Log.Warning("{Key} must be true or false, got {Value}. Using {Default} instead", key, value, defaultValue);
+        return defaultValue;
+    }
+
+    // Accepts "#E74C3C" as well as "E74C3C"
+    internal static bool TryParseColor(string value, out uint color)
+    {
+        return uint.TryParse(value.TrimStart('#'), NumberStyles.HexNumber, CultureInfo.InvariantCulture, out color) &&
+               color <= 0xFFFFFF;
+    }
+
+    internal static bool IsHttpUrl(string value)
+    {
+        return Uri.TryCreate(value, UriKind.Absolute, out var uri) &&
+               (uri.Scheme == Uri.UriSchemeHttp || uri.Scheme == Uri.UriSchemeHttps);
+    }
+
+    // Lists every value that is missing or would be ignored or reset to its default at runtime
+    internal static IReadOnlyList<string> Validate(IConfiguration configuration)
+    {
+        var problems = new List<string>();
+
+        if (ValidateToken(configuration["token"]) is { } tokenError)
+            problems.Add($"token: {tokenError}");
+
+        foreach (var key in BooleanKeys)
+            if (configuration[key] is { } value && !bool.TryParse(value, out _))
+                problems.Add($"{key}: {value} is not true or false");
+
+        foreach (var key in IntegerKeys)
+            if (configuration[key] is { } value && !int.TryParse(value, out _))
+                problems.Add($"{key}: {value} is not a whole number");
+
+        if (configuration["logging:level"] is { } level && !Enum.TryParse<LogEventLevel>(level, true, out _))
+            problems.Add($"logging:level: {level} is not a Serilog level, Verbose is used");
+
+        foreach (var module in configuration.GetSection("logging:module_levels").GetChildren())
+            if (!Enum.TryParse<LogEventLevel>(module.Value, true, out _))
+                problems.Add($"logging:module_levels:{module.Key}: {module.Value} is not a Serilog level and is ignored");
+
+        if (configuration["logging:format"] is { } format &&
+            !format.Equals("text", StringComparison.OrdinalIgnoreCase) &&
+            !format.Equals("json", StringComparison.OrdinalIgnoreCase))
+            problems.Add($"logging:format: {format} is not text or json, text is used");
+
+        if (int.TryParse(configuration["shards"], out var shards) && shards < 0)
+            problems.Add($"shards: {shards} is negative, the recommended shard count is used");
+
+        if (configuration["logging:rotation"] is { } rotation && !LoggingHandler.TryParseRotation(rotation, out _))
+            problems.Add($"logging:rotation: {rotation} is not a number of days, hours or minutes like 12h, files roll " +
+                         "every minute");
+
+        if (configuration["logging:max_files"] is { } maxFiles && (!int.TryParse(maxFiles, out var count) || count <= 0))
+            problems.Add($"logging:max_files: {maxFiles} is not a positive integer, the default is used");
+
+        if (configuration["branding:color"] is { } color && !TryParseColor(color, out _))
+            problems.Add($"branding:color: {color} is not a hex color, the default is used");
+
+        if (configuration["branding:footer_icon_url"] is { } iconUrl && !IsHttpUrl(iconUrl))
+            problems.Add($"branding:footer_icon_url: {iconUrl} is not an http(s) URL and is ignored");
+
+        if (configuration["registration:mode"] is { } mode && !Enum.TryParse<RegistrationMode>(mode, true, out _))
+            problems.Add($"registration:mode: {mode} is not global, guilds or both, global is used");
+
+        if (configuration["debug_guild_id"] is { } debugGuildId && !ulong.TryParse(debugGuildId, out _))
+            problems.Add($"debug_guild_id: {debugGuildId} is not a guild ID");
+
+        foreach (var guildId in configuration.GetSection("registration:guild_ids").GetChildren())
+            if (!ulong.TryParse(guildId.Value, out _))
+                problems.Add($"registration:guild_ids: {guildId.Value} is not a guild ID and is ignored");
+
+        if (int.TryParse(configuration["activity:interval_secs"], out var interval) && interval < 15)
+            problems.Add($"activity:interval_secs: {interval} is below the minimum of 15, 15 is used");
+
+        return problems;
+    }
+}
\ No newline at end of file
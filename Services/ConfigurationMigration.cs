@@ -0,0 +1,84 @@
+using System.Text.Json;
+using System.Text.Json.Nodes;
+
+namespace Red.Services;
+
+// Configuration files may carry a "version", files written for an older shape are upgraded in place on startup
+internal static class ConfigurationMigration
+{
+    private static readonly JsonDocumentOptions ReadOptions = new()
+    {
+        CommentHandling = JsonCommentHandling.Skip,
+        AllowTrailingCommas = true
+    };
+
+    private static readonly JsonSerializerOptions WriteOptions = new() { WriteIndented = true };
+
+    // Migrations[i] upgrades a file from version i to i + 1, so the current version is the number of migrations.
+    // Files without a version are version 0, which is the same shape as version 1. Renames and new defaults are
+    // added at the end.
+    private static readonly Action<JsonObject>[] Migrations =
+    {
+        _ => { }
+    };
+
+    // Rewrites the file only when a migration actually changed its shape, keeping the original next to it as .bak
+    // since comments don't survive the round trip. A file whose content needs no change therefore keeps its old
+    // version, or none at all, instead of being stamped. Newer files are left alone, their unknown keys are simply
+    // ignored.
+    internal static void Migrate(string path)
+    {
+        Migrate(path, Migrations);
+    }
+
+    internal static void Migrate(string path, IReadOnlyList<Action<JsonObject>> migrations)
+    {
+        if (!File.Exists(path)) return;
+
+        if (JsonNode.Parse(File.ReadAllText(path), documentOptions: ReadOptions) is not JsonObject root)
+            throw new ConfigurationException(path, "must contain a JSON object.");
+
+        var version = 0;
+        if (root["version"] is { } versionNode &&
+            (versionNode is not JsonValue value || !value.TryGetValue(out version) || version < 0))
+            throw new ConfigurationException(path, $"version must be a whole number, got {versionNode.ToJsonString()}.");
+
+        if (version > migrations.Count)
+        {
+            Console.Error.WriteLine(
+                $"{path} is for configuration version {version}, this build only knows up to {migrations.Count}");
+            return;
+        }
+
+        if (!Apply(root, version, migrations)) return;
+
+        try
+        {
+            File.Copy(path, path + ".bak", true);
+            File.WriteAllText(path, root.ToJsonString(WriteOptions));
+        }
+        catch (Exception ex) when (ex is IOException or UnauthorizedAccessException)
+        {
+            // Loading still works from the old file, it just gets migrated again next time
+            Console.Error.WriteLine($"Failed to write the migrated configuration to {path}: {ex.Message}");
+            return;
+        }
+
+        Console.Error.WriteLine($"Migrated {path} from configuration version {version} to {migrations.Count}");
+    }
+
+    // Runs the migrations from version up to the latest one on root and stamps the new version. Returns false when
+    // they changed nothing, root is then left exactly as it was so the file never has to be rewritten.
+    internal static bool Apply(JsonObject root, int version, IReadOnlyList<Action<JsonObject>> migrations)
+    {
+        var before = root.ToJsonString();
+
+        for (var i = version; i < migrations.Count; i++)
+            migrations[i](root);
+
+        if (root.ToJsonString() == before) return false;
+
+        root["version"] = migrations.Count;
+        return true;
+    }
+}
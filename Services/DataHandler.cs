@@ -0,0 +1,49 @@
+using Microsoft.Extensions.Configuration;
+using Serilog;
+
+namespace Red.Services;
+
+// Every file the bot persists lives under "data_dir", so operators only have one location to mount
+internal static class DataHandler
+{
+    private const string DefaultDataDirectory = "data";
+
+    private static string _directory = Path.GetFullPath(DefaultDataDirectory);
+
+    // Creates the data directory and makes sure we can write to it, returns false when we can't
+    internal static bool Initialize(IConfiguration configuration)
+    {
+        _directory = Path.GetFullPath(configuration["data_dir"] ?? DefaultDataDirectory);
+
+        try
+        {
+            Directory.CreateDirectory(_directory);
+
+            var probe = Path.Combine(_directory, $".write-test-{Environment.ProcessId}");
+            File.WriteAllText(probe, string.Empty);
+            File.Delete(probe);
+        }
+        catch (Exception ex) when (ex is IOException or UnauthorizedAccessException)
+        {
+            Log.Fatal(ex, "The data directory {Directory} is not writable, check data_dir in the configuration",
+                _directory);
+            return false;
+        }
+
+        Log.Information("Using data directory {Directory}", _directory);
+        return true;
+    }
+
+    // Resolves a path inside the data directory, refusing anything that would end up outside of it
+    internal static string GetPath(string relativePath)
+    {
+        var path = Path.GetFullPath(Path.Combine(_directory, relativePath));
+        var root = Path.EndsInDirectorySeparator(_directory) ? _directory : _directory + Path.DirectorySeparatorChar;
+
+        if (!path.StartsWith(root, StringComparison.Ordinal))
+            throw new ArgumentException($"{relativePath} resolves outside of the data directory",
+                nameof(relativePath));
+
+        return path;
+    }
+}
\ No newline at end of file
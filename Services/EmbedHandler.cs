@@ -0,0 +1,60 @@
+using Discord;
+using Microsoft.Extensions.Configuration;
+using Serilog;
+
+namespace Red.Services;
+
+internal static class EmbedHandler
+{
+    private static readonly Color DefaultColor = Color.Red;
+
+    private static Color _color = DefaultColor;
+    private static string? _footerText;
+    private static string? _footerIconUrl;
+
+    // Reads the "branding" section at startup and on reload, invalid values are logged and replaced by defaults
+    internal static void Initialize(IConfiguration configuration)
+    {
+        var branding = configuration.GetSection("branding");
+        _color = DefaultColor;
+        _footerIconUrl = null;
+
+        var color = branding["color"];
+        if (color is not null)
+        {
+            if (ConfigurationHandler.TryParseColor(color, out var rawColor))
+                _color = new Color(rawColor);
+            else
+                Log.Warning("branding:color must be a hex color like #E74C3C, got {Value}. Using the default",
+                    color);
+        }
+
+        _footerText = branding["footer_text"];
+
+        var footerIconUrl = branding["footer_icon_url"];
+        if (footerIconUrl is null) return;
+
+        if (ConfigurationHandler.IsHttpUrl(footerIconUrl))
+            _footerIconUrl = footerIconUrl;
+        else
+            Log.Warning("branding:footer_icon_url must be an http(s) URL, got {Value}. Ignoring it", footerIconUrl);
+    }
+
+    // Every embed the bot sends should start from here so they all share the same branding
+    internal static EmbedBuilder BaseEmbed()
+    {
+        var embed = new EmbedBuilder().WithColor(_color);
+
+        if (_footerText is not null || _footerIconUrl is not null)
+            embed.WithFooter(_footerText, _footerIconUrl);
+
+        return embed;
+    }
+
+    internal static EmbedBuilder ErrorEmbed(string message)
+    {
+        return BaseEmbed()
+            .WithTitle("Command failed")
+            .WithDescription(message);
+    }
+}
\ No newline at end of file
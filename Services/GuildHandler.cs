@@ -0,0 +1,58 @@
+using Discord.WebSocket;
+using Microsoft.Extensions.Configuration;
+using Serilog;
+
+namespace Red.Services;
+
+public class GuildHandler
+{
+    private const string DefaultWelcomeMessage =
+        "Thanks for adding me to {guild}! Type `/` to browse my commands, or use `/invite` to add me elsewhere.";
+
+    private readonly DiscordShardedClient _client;
+    private readonly IConfiguration _configuration;
+
+    public GuildHandler(DiscordShardedClient client, IConfiguration configuration)
+    {
+        _client = client;
+        _configuration = configuration;
+    }
+
+    public void Initialize()
+    {
+        _client.JoinedGuild += JoinedGuild;
+        _client.LeftGuild += guild =>
+        {
+            Log.Information("Left guild {GuildName} ({GuildId})", guild.Name, guild.Id);
+            return Task.CompletedTask;
+        };
+    }
+
+    // Posts "welcome:message" to the new guild's system channel, {guild} and {bot} are replaced by their names
+    private async Task JoinedGuild(SocketGuild guild)
+    {
+        Log.Information("Joined guild {GuildName} ({GuildId})", guild.Name, guild.Id);
+
+        if (!ConfigurationHandler.GetBool(_configuration, "welcome:enabled", true)) return;
+
+        var channel = guild.SystemChannel;
+        if (channel is null || !guild.CurrentUser.GetPermissions(channel).SendMessages)
+        {
+            Log.Information("Not sending a welcome message to {GuildId}, no system channel we can write to", guild.Id);
+            return;
+        }
+
+        var message = (_configuration["welcome:message"] ?? DefaultWelcomeMessage)
+            .Replace("{guild}", guild.Name)
+            .Replace("{bot}", _client.CurrentUser.Username);
+
+        try
+        {
+            await channel.SendMessageAsync(message);
+        }
+        catch (Exception ex)
+        {
+            Log.Warning(ex, "Failed to send the welcome message to {GuildId}", guild.Id);
+        }
+    }
+}
\ No newline at end of file
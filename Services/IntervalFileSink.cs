@@ -0,0 +1,93 @@
+using Serilog.Core;
+using Serilog.Debugging;
+using Serilog.Events;
+using Serilog.Sinks.File;
+
+namespace Red.Services;
+
+// Serilog's file sink only rolls on a single day, hour or minute. This one starts "<name><yyyyMMddHHmm>.txt" every
+// interval instead, each period getting its own file logger, and keeps the newest maxFiles files like the sink's own
+// retention does.
+internal sealed class IntervalFileSink : ILogEventSink, IDisposable
+{
+    private readonly Func<string, Logger> _createLogger;
+    private readonly string _directory;
+    private readonly FileLifecycleHooks? _hooks;
+    private readonly TimeSpan _interval;
+    private readonly object _lock = new();
+    private readonly int _maxFiles;
+    private readonly string _name;
+    private Logger? _current;
+    private long _period = -1;
+
+    public IntervalFileSink(string directory, string name, TimeSpan interval, int maxFiles, FileLifecycleHooks? hooks,
+        Func<string, Logger> createLogger)
+    {
+        _directory = directory;
+        _name = name;
+        _interval = interval;
+        _maxFiles = maxFiles;
+        _hooks = hooks;
+        _createLogger = createLogger;
+    }
+
+    public void Emit(LogEvent logEvent)
+    {
+        lock (_lock)
+        {
+            // Periods are counted in local time like the calendar units, so 12h rolls at midnight and noon
+            var period = logEvent.Timestamp.LocalDateTime.Ticks / _interval.Ticks;
+            if (_current is null || period != _period)
+                Roll(period);
+
+            _current!.Write(logEvent);
+        }
+    }
+
+    public void Dispose()
+    {
+        lock (_lock)
+        {
+            _current?.Dispose();
+            _current = null;
+        }
+    }
+
+    private void Roll(long period)
+    {
+        _current?.Dispose();
+
+        var start = new DateTime(period * _interval.Ticks);
+        _current = _createLogger(Path.Combine(_directory, $"{_name}{start:yyyyMMddHHmm}.txt"));
+        _period = period;
+
+        ApplyRetention();
+    }
+
+    // Same order as the hooks expect from the file sink, OnFileDeleting first and a throwing hook keeps the file
+    private void ApplyRetention()
+    {
+        string[] files;
+        try
+        {
+            files = Directory.GetFiles(_directory, $"{_name}*.txt");
+        }
+        catch (Exception ex) when (ex is IOException or UnauthorizedAccessException)
+        {
+            SelfLog.WriteLine("Failed to list log files in {0}: {1}", _directory, ex);
+            return;
+        }
+
+        // The timestamp in the name sorts chronologically, size rolled "_001" files after the one they continue
+        foreach (var file in files.OrderByDescending(x => x, StringComparer.Ordinal).Skip(_maxFiles))
+            try
+            {
+                _hooks?.OnFileDeleting(file);
+                File.Delete(file);
+            }
+            catch (Exception ex)
+            {
+                SelfLog.WriteLine("Failed to remove old log file {0}: {1}", file, ex);
+            }
+    }
+}
\ No newline at end of file
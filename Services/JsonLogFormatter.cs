@@ -0,0 +1,46 @@
+using System.Text.Json;
+using System.Text.Json.Serialization;
+using Serilog.Events;
+using Serilog.Formatting;
+using Serilog.Formatting.Display;
+
+namespace Red.Services;
+
+// Writes every event as a single line {"timestamp", "level", "target", "message"} object for log shippers
+internal class JsonLogFormatter : ITextFormatter
+{
+    private static readonly JsonSerializerOptions Options = new()
+    {
+        DefaultIgnoreCondition = JsonIgnoreCondition.WhenWritingNull
+    };
+
+    // Renders the message the same way the text log does, without quoting string properties
+    private static readonly MessageTemplateTextFormatter MessageFormatter = new("{Message:lj}");
+
+    public void Format(LogEvent logEvent, TextWriter output)
+    {
+        var message = new StringWriter();
+        MessageFormatter.Format(logEvent, message);
+
+        var entry = new
+        {
+            timestamp = logEvent.Timestamp.ToString("O"),
+            level = logEvent.Level.ToString(),
+            target = GetTarget(logEvent),
+            message = message.ToString(),
+            exception = logEvent.Exception?.ToString()
+        };
+
+        output.WriteLine(JsonSerializer.Serialize(entry, Options));
+    }
+
+    // Discord.Net messages carry their source, everything else is logged by the bot itself
+    private static string GetTarget(LogEvent logEvent)
+    {
+        foreach (var name in new[] { "Source", "SourceContext" })
+            if (logEvent.Properties.TryGetValue(name, out var value) && value is ScalarValue { Value: string target })
+                return target;
+
+        return "Red";
+    }
+}
\ No newline at end of file
@@ -0,0 +1,153 @@
+using System.Diagnostics;
+using System.Reflection;
+using System.Runtime.InteropServices;
+using Discord.WebSocket;
+using Serilog;
+
+namespace Red.Services;
+
+public class LifetimeHandler
+{
+    // Returned when the process can't replace itself, supervisors should treat it as "start me again"
+    public const int RestartExitCode = 75;
+
+    // Gives commands that are still running a moment to send their responses before we disconnect
+    private static readonly TimeSpan GracePeriod = TimeSpan.FromSeconds(2);
+
+    private readonly DiscordShardedClient _client;
+    private readonly CancellationTokenSource _shutdown = new();
+    private readonly TaskCompletionSource _signalled = new(TaskCreationOptions.RunContinuationsAsynchronously);
+    private int _signals;
+
+    // Must stay referenced, disposing the registration restores the default SIGTERM behavior
+    private PosixSignalRegistration? _sigtermRegistration;
+
+    public LifetimeHandler(DiscordShardedClient client)
+    {
+        _client = client;
+    }
+
+    // Cancelled once a shutdown signal arrives, so startup steps like login retries can stop waiting
+    public CancellationToken ShutdownToken => _shutdown.Token;
+
+    // Hooks Ctrl-C/SIGINT and SIGTERM, call this before anything slow happens at startup. The first signal is
+    // cancelled so the runtime doesn't terminate the process before we had the chance to shut down cleanly,
+    // a second one is let through and ends the process right away.
+    public void RegisterShutdownSignals()
+    {
+        Console.CancelKeyPress += (_, e) => e.Cancel = OnSignal("Ctrl-C");
+
+        if (!OperatingSystem.IsWindows())
+            _sigtermRegistration = PosixSignalRegistration.Create(PosixSignal.SIGTERM,
+                context => context.Cancel = OnSignal("SIGTERM"));
+    }
+
+    // Completes once the first shutdown signal is received
+    public Task WaitForShutdownSignalAsync()
+    {
+        return _signalled.Task;
+    }
+
+    // Returns whether the runtime's default handling, terminating the process, should be suppressed
+    private bool OnSignal(string signal)
+    {
+        if (Interlocked.Increment(ref _signals) > 1)
+        {
+            Log.Warning("Received {Signal} again, terminating immediately", signal);
+            Log.CloseAndFlush();
+            return false;
+        }
+
+        Log.Information("Received {Signal}, shutting down", signal);
+        _signalled.TrySetResult();
+        _shutdown.Cancel();
+        return true;
+    }
+
+    public async Task ShutdownAsync()
+    {
+        await DisconnectAsync();
+
+        Log.Information("Shutdown complete, flushing logs");
+        Log.CloseAndFlush();
+    }
+
+    public async Task RestartAsync()
+    {
+        Log.Information("Restart requested");
+        await DisconnectAsync();
+
+        var exitCode = RestartExitCode;
+        if (!CanRespawn())
+        {
+            Log.Information("Can't respawn in this environment, exiting with code {Code}", RestartExitCode);
+        }
+        else
+        {
+            try
+            {
+                Process.Start(CreateRespawnInfo());
+                Log.Information("Started a new instance, exiting");
+                exitCode = 0;
+            }
+            catch (Exception ex)
+            {
+                Log.Error(ex, "Failed to start a new instance, exiting with code {Code}", RestartExitCode);
+            }
+        }
+
+        Log.CloseAndFlush();
+        Environment.Exit(exitCode);
+    }
+
+    // Each step is logged so operators can follow the shutdown, a failing step doesn't prevent the log flush
+    private async Task DisconnectAsync()
+    {
+        Log.Information("Waiting {Seconds} s for running commands to respond...", GracePeriod.TotalSeconds);
+        await Task.Delay(GracePeriod);
+
+        try
+        {
+            Log.Information("Closing the gateway connection...");
+            await _client.StopAsync();
+
+            Log.Information("Logging out...");
+            await _client.LogoutAsync();
+        }
+        catch (Exception ex)
+        {
+            Log.Error(ex, "Failed to disconnect cleanly from Discord");
+        }
+    }
+
+    // Inside a container our process is PID 1, a child outliving it would be killed together with the container.
+    // Supervisors do the same: systemd stops the whole control group once we exit and supervisord loses track of
+    // the child, so we leave the restart to them by exiting with RestartExitCode (e.g. Restart=on-failure).
+    private static bool CanRespawn()
+    {
+        return Environment.ProcessPath is not null &&
+               Environment.GetEnvironmentVariable("DOTNET_RUNNING_IN_CONTAINER") != "true" &&
+               !IsSupervised();
+    }
+
+    // systemd sets INVOCATION_ID for every service it starts, supervisord sets SUPERVISOR_ENABLED
+    private static bool IsSupervised()
+    {
+        return !string.IsNullOrEmpty(Environment.GetEnvironmentVariable("INVOCATION_ID")) ||
+               !string.IsNullOrEmpty(Environment.GetEnvironmentVariable("SUPERVISOR_ENABLED"));
+    }
+
+    private static ProcessStartInfo CreateRespawnInfo()
+    {
+        var info = new ProcessStartInfo(Environment.ProcessPath!) { UseShellExecute = false };
+
+        // When started through "dotnet Red.dll" the host needs the assembly path as its first argument
+        if (Path.GetFileNameWithoutExtension(Environment.ProcessPath) == "dotnet")
+            info.ArgumentList.Add(Assembly.GetEntryAssembly()!.Location);
+
+        foreach (var argument in Environment.GetCommandLineArgs().Skip(1))
+            info.ArgumentList.Add(argument);
+
+        return info;
+    }
+}
\ No newline at end of file
@@ -1,26 +1,228 @@
 ﻿using Discord;
+using Discord.Interactions;
+using Microsoft.Extensions.Configuration;
 using Serilog;
+using System.Globalization;
+using Serilog.Configuration;
+using Serilog.Core;
+using Serilog.Debugging;
 using Serilog.Events;
+using Serilog.Sinks.File;
+using Serilog.Sinks.SystemConsole.Themes;
 
 namespace Red.Services;
 
 internal static class LoggingHandler
 {
-    internal static void Serilog()
-    {
-        Log.Logger = new LoggerConfiguration()
-            .MinimumLevel.Verbose()
-            .MinimumLevel.Override("Microsoft", LogEventLevel.Warning)
-            .WriteTo.Console(outputTemplate:
-                "[{Timestamp:HH:mm:ss} {Level:u3}] {Message:lj}{NewLine}{Exception}")
-            .WriteTo.File("logs/log.txt",
-                outputTemplate: "{Timestamp:dd-MM-yyyy HH:mm:ss.fff zzz} [{Level:u3}] {Message:lj}{NewLine}{Exception}",
-                rollingInterval: RollingInterval.Minute,
-                rollOnFileSizeLimit: true)
-            .CreateLogger();
+    // Serilog's own default for retainedFileCountLimit
+    private const int DefaultMaxFiles = 31;
+
+    private const string DefaultRotationValue = "1m";
+
+    private static readonly TimeSpan DefaultRotation = TimeSpan.FromMinutes(1);
+
+    // Keeps the interval arithmetic away from TimeSpan's limits, nobody rotates less than once a year
+    private static readonly TimeSpan MaxRotation = TimeSpan.FromDays(366);
+
+    private const LogEventLevel DefaultLevel = LogEventLevel.Verbose;
+
+    // Minimum level of the main logger, can be changed at runtime through the loglevel and reload commands
+    internal static readonly LoggingLevelSwitch LevelSwitch = new(DefaultLevel);
+
+    // Separate logger for the command audit trail, null unless "logging:audit_commands" is enabled
+    private static ILogger? _commandLogger;
+
+    internal static void Serilog(IConfiguration configuration)
+    {
+        // Colors are already dropped when stdout is redirected, NO_COLOR (https://no-color.org) disables them everywhere
+        // and "force_color" keeps them for color-aware tools reading from a pipe.
+        var forceColor = ConfigurationHandler.GetBool(configuration, "logging:force_color");
+        var noColor = !forceColor && !string.IsNullOrEmpty(Environment.GetEnvironmentVariable("NO_COLOR"));
+
+        // Keep at most "max_files" rolled files per log, the oldest ones are deleted first
+        var maxFilesValue = configuration["logging:max_files"];
+        var maxFiles = DefaultMaxFiles;
+        var maxFilesInvalid = maxFilesValue is not null && (!int.TryParse(maxFilesValue, out maxFiles) || maxFiles <= 0);
+        if (maxFilesInvalid)
+            maxFiles = DefaultMaxFiles;
+
+        // How often a new log file is started, a number of days, hours or minutes like "1d", "12h" or "30m"
+        var rotationValue = configuration["logging:rotation"];
+        var rotation = DefaultRotation;
+        var rotationInvalid = rotationValue is not null && !TryParseRotation(rotationValue, out rotation);
+        if (rotationInvalid)
+            rotation = DefaultRotation;
+
+        var levelValue = configuration["logging:level"];
+        var levelInvalid = !ApplyLevel(configuration);
+
+        // "module_levels" maps a source prefix (e.g. "Gateway" or "Microsoft") to its own minimum level
+        var moduleLevels = new Dictionary<string, LogEventLevel>();
+        var invalidModuleLevels = new List<(string Module, string? Value)>();
+        foreach (var module in configuration.GetSection("logging:module_levels").GetChildren())
+            if (Enum.TryParse<LogEventLevel>(module.Value, true, out var moduleLevel))
+                moduleLevels[module.Key] = moduleLevel;
+            else
+                invalidModuleLevels.Add((module.Key, module.Value));
+
+        // Daemonized deployments may have nobody reading stdout, once the pipe is full every log call would block
+        var console = ConfigurationHandler.GetBool(configuration, "logging:console", true);
+
+        // Problems inside the sinks themselves, like failing to archive a log file, are reported on stderr.
+        // Without the console sink nobody is expected to read it, so the same reasoning applies.
+        if (console)
+            SelfLog.Enable(Console.Error);
+
+        var hooks = ConfigurationHandler.GetBool(configuration, "logging:compress") ? new ArchiveHooks(maxFiles) : null;
+
+        // "json" writes one object per line to log.txt for log shippers, the console always stays human readable
+        var format = configuration["logging:format"] ?? "text";
+        var json = format.Equals("json", StringComparison.OrdinalIgnoreCase);
+        var formatInvalid = !json && !format.Equals("text", StringComparison.OrdinalIgnoreCase);
+
+        var logger = new LoggerConfiguration()
+            .MinimumLevel.ControlledBy(LevelSwitch)
+            .MinimumLevel.Override("Microsoft", LogEventLevel.Warning);
+
+        foreach (var (module, moduleLevel) in moduleLevels)
+            logger.MinimumLevel.Override(module, moduleLevel);
+
+        if (json)
+            WriteToFile(logger, "log", rotation, maxFiles, hooks, (to, path, interval, retained) =>
+                to.File(new JsonLogFormatter(), path,
+                    rollingInterval: interval,
+                    rollOnFileSizeLimit: true,
+                    retainedFileCountLimit: retained,
+                    hooks: hooks));
+        else
+            WriteToFile(logger, "log", rotation, maxFiles, hooks, (to, path, interval, retained) =>
+                to.File(path,
+                    outputTemplate:
+                    "{Timestamp:dd-MM-yyyy HH:mm:ss.fff zzz} [{Level:u3}] {Message:lj}{NewLine}{Exception}",
+                    rollingInterval: interval,
+                    rollOnFileSizeLimit: true,
+                    retainedFileCountLimit: retained,
+                    hooks: hooks));
+
+        if (console)
+            logger.WriteTo.Console(outputTemplate:
+                "[{Timestamp:HH:mm:ss} {Level:u3}] {Message:lj}{NewLine}{Exception}",
+                theme: noColor ? ConsoleTheme.None : null,
+                applyThemeToRedirectedOutput: forceColor);
+
+        Log.Logger = logger.CreateLogger();
+
+        // One line per invocation with every value quoted, so the file can be parsed as key=value pairs
+        if (ConfigurationHandler.GetBool(configuration, "logging:audit_commands"))
+        {
+            var commandLogger = new LoggerConfiguration().MinimumLevel.Information();
+            WriteToFile(commandLogger, "commands", rotation, maxFiles, hooks, (to, path, interval, retained) =>
+                to.File(path,
+                    outputTemplate: "{Timestamp:dd-MM-yyyy HH:mm:ss.fff zzz} {Message}{NewLine}",
+                    rollingInterval: interval,
+                    rollOnFileSizeLimit: true,
+                    retainedFileCountLimit: retained,
+                    hooks: hooks));
+            _commandLogger = commandLogger.CreateLogger();
+        }
 
         Log.Information("Red starting!");
         Log.Information("Logging initialized!");
+
+        if (levelInvalid)
+            Log.Warning("logging:level must be one of {Levels}, got {Value}. Using {Default} instead",
+                Enum.GetNames<LogEventLevel>(), levelValue, LevelSwitch.MinimumLevel);
+
+        foreach (var (module, value) in invalidModuleLevels)
+            Log.Warning("logging:module_levels:{Module} must be one of {Levels}, got {Value}. Ignoring it",
+                module, Enum.GetNames<LogEventLevel>(), value);
+
+        // These are read before the logger exists, so GetBool's own warning went nowhere
+        foreach (var key in new[] { "logging:force_color", "logging:compress", "logging:console" })
+            if (configuration[key] is { } value && !bool.TryParse(value, out _))
+                Log.Warning("{Key} must be true or false, got {Value}. Using the default instead", key, value);
+
+        if (formatInvalid)
+            Log.Warning("logging:format must be text or json, got {Value}. Using text instead", format);
+
+        if (rotationInvalid)
+            Log.Warning(
+                "logging:rotation must be a number of days, hours or minutes like 1d, 12h or 30m, got {Value}. " +
+                "Using {Default} instead", rotationValue, DefaultRotationValue);
+
+        if (maxFilesInvalid)
+            Log.Warning("logging:max_files must be a positive integer, got {Value}. Using {Default} instead",
+                maxFilesValue, DefaultMaxFiles);
+    }
+
+    // Sets LevelSwitch from "logging:level", returns false when the value is invalid and was ignored
+    internal static bool ApplyLevel(IConfiguration configuration)
+    {
+        var value = configuration["logging:level"];
+        if (value is null)
+        {
+            LevelSwitch.MinimumLevel = DefaultLevel;
+            return true;
+        }
+
+        if (!Enum.TryParse<LogEventLevel>(value, true, out var level)) return false;
+
+        LevelSwitch.MinimumLevel = level;
+        return true;
+    }
+
+    // Parses "<n>d", "<n>h" or "<n>m" into an interval, a unit without a number counts as one
+    internal static bool TryParseRotation(string value, out TimeSpan rotation)
+    {
+        rotation = TimeSpan.Zero;
+
+        var trimmed = value.Trim().ToLowerInvariant();
+        if (trimmed.Length == 0) return false;
+
+        var unit = trimmed[^1] switch
+        {
+            'd' => TimeSpan.FromDays(1),
+            'h' => TimeSpan.FromHours(1),
+            'm' => TimeSpan.FromMinutes(1),
+            _ => TimeSpan.Zero
+        };
+
+        if (unit == TimeSpan.Zero) return false;
+
+        var count = 1;
+        if (trimmed.Length > 1 &&
+            (!int.TryParse(trimmed[..^1], NumberStyles.None, CultureInfo.InvariantCulture, out count) || count <= 0 ||
+             count > MaxRotation / unit))
+            return false;
+
+        rotation = unit * count;
+        return true;
+    }
+
+    // Serilog rolls on a single calendar unit by itself, null when the interval needs an IntervalFileSink
+    internal static RollingInterval? ToRollingInterval(TimeSpan rotation)
+    {
+        if (rotation == TimeSpan.FromDays(1)) return RollingInterval.Day;
+        if (rotation == TimeSpan.FromHours(1)) return RollingInterval.Hour;
+        if (rotation == TimeSpan.FromMinutes(1)) return RollingInterval.Minute;
+        return null;
+    }
+
+    // Adds a file sink for logs/<name>.txt starting a new file every rotation. file receives where to write to, the
+    // path, Serilog's rolling interval and the retained file count, and adds the actual file sink.
+    private static void WriteToFile(LoggerConfiguration logger, string name, TimeSpan rotation, int maxFiles,
+        FileLifecycleHooks? hooks, Func<LoggerSinkConfiguration, string, RollingInterval, int?, LoggerConfiguration> file)
+    {
+        if (ToRollingInterval(rotation) is { } interval)
+        {
+            file(logger.WriteTo, $"logs/{name}.txt", interval, maxFiles);
+            return;
+        }
+
+        // The outer logger already applied the levels and overrides, every event reaching the sink is written
+        logger.WriteTo.Sink(new IntervalFileSink("logs", name, rotation, maxFiles, hooks, path =>
+            file(new LoggerConfiguration().MinimumLevel.Verbose().WriteTo, path, RollingInterval.Infinite, null)
+                .CreateLogger()));
     }
 
     internal static Task LogAsync(LogMessage message)
@@ -36,9 +238,39 @@ internal static class LoggingHandler
             _ => LogEventLevel.Information
         };
 
-        Log.Write(severity, message.Exception, "[{Source}] {Message}",
-            message.Source, message.Message);
+        // The source doubles as the context so "logging:module_levels" can target it
+        Log.ForContext(Constants.SourceContextPropertyName, message.Source)
+            .Write(severity, message.Exception, "[{Source}] {Message}", message.Source, message.Message);
 
         return Task.CompletedTask;
     }
+
+    internal static void LogCommand(string command, IInteractionContext context, IResult result)
+    {
+        _commandLogger?.Information(
+            "command={Command} user={UserId} guild={GuildId} channel={ChannelId} args={Arguments} outcome={Outcome}",
+            command, context.User.Id, context.Guild?.Id, context.Channel?.Id, FormatArguments(context.Interaction),
+            result.IsSuccess ? "success" : $"{result.Error}: {result.ErrorReason}");
+    }
+
+    private static string FormatArguments(IDiscordInteraction interaction)
+    {
+        return interaction switch
+        {
+            ISlashCommandInteraction slash => string.Join(" ", FlattenOptions(slash.Data.Options)),
+            IUserCommandInteraction user => $"user={user.Data.User.Id}",
+            IMessageCommandInteraction message => $"message={message.Data.Message.Id}",
+            IComponentInteraction component => string.Join(" ",
+                component.Data.Values?.Prepend(component.Data.CustomId) ?? new[] { component.Data.CustomId }),
+            _ => string.Empty
+        };
+    }
+
+    private static IEnumerable<string> FlattenOptions(IEnumerable<IApplicationCommandInteractionDataOption> options)
+    {
+        return options.SelectMany(x =>
+            x.Type is ApplicationCommandOptionType.SubCommand or ApplicationCommandOptionType.SubCommandGroup
+                ? FlattenOptions(x.Options)
+                : new[] { $"{x.Name}={x.Value}" });
+    }
 }
\ No newline at end of file
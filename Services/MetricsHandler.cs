@@ -0,0 +1,112 @@
+using System.Collections.Concurrent;
+using System.Diagnostics;
+using Microsoft.Extensions.Configuration;
+using Serilog;
+
+namespace Red.Services;
+
+public class CommandStats
+{
+    // Upper bounds of the latency histogram buckets in milliseconds, anything slower lands in the last bucket
+    public static readonly double[] BucketBounds = { 50, 100, 250, 500, 1000, 2500, 5000 };
+
+    private readonly long[] _buckets = new long[BucketBounds.Length + 1];
+    private long _failures;
+    private long _invocations;
+    private long _successes;
+    private long _timed;
+    private long _totalTicks;
+
+    public long Invocations => Interlocked.Read(ref _invocations);
+    public long Successes => Interlocked.Read(ref _successes);
+    public long Failures => Interlocked.Read(ref _failures);
+
+    // Invocations with a known duration, the histogram and TotalDuration only cover these
+    public long Timed => Interlocked.Read(ref _timed);
+    public TimeSpan TotalDuration => TimeSpan.FromTicks(Interlocked.Read(ref _totalTicks));
+
+    // Non-cumulative counts per bucket, index i counts latencies up to BucketBounds[i]
+    public IReadOnlyList<long> Buckets =>
+        Enumerable.Range(0, _buckets.Length).Select(x => Interlocked.Read(ref _buckets[x])).ToArray();
+
+    // A null duration still counts the invocation but adds no latency sample
+    internal void Record(bool success, TimeSpan? duration)
+    {
+        Interlocked.Increment(ref _invocations);
+        if (success)
+            Interlocked.Increment(ref _successes);
+        else
+            Interlocked.Increment(ref _failures);
+
+        if (duration is not { } elapsed) return;
+
+        Interlocked.Increment(ref _timed);
+        Interlocked.Add(ref _totalTicks, elapsed.Ticks);
+
+        var bucket = Array.FindIndex(BucketBounds, x => elapsed.TotalMilliseconds <= x);
+        Interlocked.Increment(ref _buckets[bucket < 0 ? BucketBounds.Length : bucket]);
+    }
+}
+
+public class MetricsHandler
+{
+    private const int DefaultSummaryIntervalMinutes = 60;
+
+    // Interactions that never reach a command (autocomplete, unknown commands) are dropped after this long
+    private const int MaxPendingInteractions = 1000;
+    private static readonly TimeSpan PendingTimeout = TimeSpan.FromMinutes(15);
+
+    private readonly IConfiguration _configuration;
+    private readonly ConcurrentDictionary<ulong, long> _started = new();
+    private readonly ConcurrentDictionary<string, CommandStats> _stats = new();
+
+    public MetricsHandler(IConfiguration configuration)
+    {
+        _configuration = configuration;
+    }
+
+    public IReadOnlyDictionary<string, CommandStats> Commands => _stats;
+
+    // Commands run asynchronously, so we remember when each interaction came in to time it once it finished
+    public void InteractionReceived(ulong interactionId)
+    {
+        var now = Stopwatch.GetTimestamp();
+        _started[interactionId] = now;
+
+        if (_started.Count <= MaxPendingInteractions) return;
+
+        var cutoff = now - (long)(PendingTimeout.TotalSeconds * Stopwatch.Frequency);
+        foreach (var (id, started) in _started)
+            if (started < cutoff)
+                _started.TryRemove(id, out _);
+    }
+
+    public void CommandExecuted(string command, ulong interactionId, bool success)
+    {
+        // The start is missing when the interaction was pruned or came in before the handler existed
+        TimeSpan? duration = _started.TryRemove(interactionId, out var started)
+            ? TimeSpan.FromSeconds((Stopwatch.GetTimestamp() - started) / (double)Stopwatch.Frequency)
+            : null;
+
+        _stats.GetOrAdd(command, _ => new CommandStats()).Record(success, duration);
+    }
+
+    // Logs a per-command summary every "metrics:summary_interval_mins" minutes, 0 disables it
+    public void StartSummaryLogging()
+    {
+        var minutes = ConfigurationHandler.GetInt(_configuration, "metrics:summary_interval_mins",
+            DefaultSummaryIntervalMinutes);
+        if (minutes <= 0) return;
+
+        _ = Task.Run(async () =>
+        {
+            using var timer = new PeriodicTimer(TimeSpan.FromMinutes(minutes));
+            while (await timer.WaitForNextTickAsync())
+                foreach (var (command, stats) in _stats.OrderBy(x => x.Key))
+                    Log.Information(
+                        "Command {Command}: {Invocations} runs, {Successes} succeeded, {Failures} failed, {Average:0} ms average",
+                        command, stats.Invocations, stats.Successes, stats.Failures,
+                        stats.Timed == 0 ? 0 : stats.TotalDuration.TotalMilliseconds / stats.Timed);
+        });
+    }
+}
\ No newline at end of file
@@ -0,0 +1,169 @@
+using System.Net;
+using System.Text;
+using Microsoft.Extensions.Configuration;
+using Serilog;
+using static System.FormattableString;
+
+namespace Red.Services;
+
+// Serves the command and shard metrics in the Prometheus text format on "metrics:port" when "metrics:prometheus" is set
+public class PrometheusHandler
+{
+    private const int DefaultPort = 9091;
+
+    private readonly IConfiguration _configuration;
+    private readonly MetricsHandler _metrics;
+    private readonly ShardHandler _shards;
+
+    public PrometheusHandler(IConfiguration configuration, MetricsHandler metrics, ShardHandler shards)
+    {
+        _configuration = configuration;
+        _metrics = metrics;
+        _shards = shards;
+    }
+
+    public void Start()
+    {
+        if (!ConfigurationHandler.GetBool(_configuration, "metrics:prometheus")) return;
+
+        var port = ConfigurationHandler.GetInt(_configuration, "metrics:port", DefaultPort);
+        var listener = new HttpListener();
+        listener.Prefixes.Add($"http://+:{port}/");
+
+        try
+        {
+            listener.Start();
+        }
+        catch (HttpListenerException ex)
+        {
+            Log.Error(ex, "Failed to serve Prometheus metrics on port {Port}", port);
+            return;
+        }
+
+        Log.Information("Serving Prometheus metrics on port {Port}", port);
+
+        _ = Task.Run(async () =>
+        {
+            while (listener.IsListening)
+            {
+                HttpListenerContext context;
+                try
+                {
+                    context = await listener.GetContextAsync();
+                }
+                catch (Exception ex) when (ex is HttpListenerException or ObjectDisposedException &&
+                                           !listener.IsListening)
+                {
+                    // The listener was closed while shutting down
+                    break;
+                }
+                catch (Exception ex)
+                {
+                    Log.Warning(ex, "Failed to accept a metrics request");
+                    continue;
+                }
+
+                await RespondAsync(context);
+            }
+        });
+    }
+
+    private async Task RespondAsync(HttpListenerContext context)
+    {
+        try
+        {
+            if (context.Request.Url?.AbsolutePath != "/metrics")
+            {
+                context.Response.StatusCode = 404;
+                return;
+            }
+
+            var body = Encoding.UTF8.GetBytes(Render());
+            context.Response.ContentType = "text/plain; version=0.0.4";
+            context.Response.ContentLength64 = body.Length;
+            await context.Response.OutputStream.WriteAsync(body);
+        }
+        catch (Exception ex)
+        {
+            Log.Warning(ex, "Failed to answer a metrics request");
+        }
+        finally
+        {
+            try
+            {
+                context.Response.Close();
+            }
+            catch (Exception ex)
+            {
+                // The scraper may have hung up already, the next scrape gets a fresh connection
+                Log.Debug(ex, "Failed to close a metrics response");
+            }
+        }
+    }
+
+    private string Render()
+    {
+        var output = new StringBuilder();
+        var commands = _metrics.Commands.OrderBy(x => x.Key).ToList();
+
+        output.AppendLine("# HELP red_commands_total Commands executed, by command and outcome");
+        output.AppendLine("# TYPE red_commands_total counter");
+        foreach (var (command, stats) in commands)
+        {
+            var label = Escape(command);
+            output.AppendLine(Invariant($"red_commands_total{{command=\"{label}\",outcome=\"success\"}} {stats.Successes}"));
+            output.AppendLine(Invariant($"red_commands_total{{command=\"{label}\",outcome=\"failure\"}} {stats.Failures}"));
+        }
+
+        output.AppendLine(
+            "# HELP red_command_duration_seconds Time from receiving an interaction to the command finishing");
+        output.AppendLine("# TYPE red_command_duration_seconds histogram");
+        foreach (var (command, stats) in commands)
+        {
+            // Prometheus buckets are cumulative, ours count each range separately
+            var label = Escape(command);
+            var buckets = stats.Buckets;
+            long cumulative = 0;
+            for (var i = 0; i < CommandStats.BucketBounds.Length; i++)
+            {
+                cumulative += buckets[i];
+                var bound = CommandStats.BucketBounds[i] / 1000;
+                output.AppendLine(Invariant(
+                    $"red_command_duration_seconds_bucket{{command=\"{label}\",le=\"{bound}\"}} {cumulative}"));
+            }
+
+            cumulative += buckets[^1];
+            output.AppendLine(Invariant(
+                $"red_command_duration_seconds_bucket{{command=\"{label}\",le=\"+Inf\"}} {cumulative}"));
+            output.AppendLine(Invariant(
+                $"red_command_duration_seconds_sum{{command=\"{label}\"}} {stats.TotalDuration.TotalSeconds}"));
+            output.AppendLine(Invariant($"red_command_duration_seconds_count{{command=\"{label}\"}} {cumulative}"));
+        }
+
+        var shards = _shards.GetStatuses();
+
+        output.AppendLine("# HELP red_gateway_latency_seconds Heartbeat latency per shard, missing before the first one");
+        output.AppendLine("# TYPE red_gateway_latency_seconds gauge");
+        foreach (var shard in shards.Where(x => x.Latency > 0))
+            output.AppendLine(
+                Invariant($"red_gateway_latency_seconds{{shard=\"{shard.Id}\"}} {shard.Latency / 1000.0}"));
+
+        output.AppendLine("# HELP red_shard_consecutive_disconnects Disconnects since the shard last connected");
+        output.AppendLine("# TYPE red_shard_consecutive_disconnects gauge");
+        foreach (var shard in shards)
+            output.AppendLine(
+                Invariant($"red_shard_consecutive_disconnects{{shard=\"{shard.Id}\"}} {shard.ConsecutiveDisconnects}"));
+
+        output.AppendLine("# HELP red_guilds Guilds served per shard");
+        output.AppendLine("# TYPE red_guilds gauge");
+        foreach (var shard in shards)
+            output.AppendLine(Invariant($"red_guilds{{shard=\"{shard.Id}\"}} {shard.GuildCount}"));
+
+        return output.ToString();
+    }
+
+    private static string Escape(string value)
+    {
+        return value.Replace("\\", "\\\\").Replace("\"", "\\\"").Replace("\n", "\\n");
+    }
+}
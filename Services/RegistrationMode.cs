@@ -0,0 +1,9 @@
+namespace Red.Services;
+
+// Where commands are registered outside of debug mode
+public enum RegistrationMode
+{
+    Global,
+    Guilds,
+    Both
+}
@@ -0,0 +1,56 @@
+using System.Net;
+using Discord.Net;
+using Microsoft.Extensions.Configuration;
+using Serilog;
+
+namespace Red.Services;
+
+internal static class RetryHandler
+{
+    private const int DefaultAttempts = 5;
+
+    private static readonly TimeSpan InitialDelay = TimeSpan.FromSeconds(1);
+    private static readonly TimeSpan MaxDelay = TimeSpan.FromSeconds(30);
+
+    // "startup_retries" is the total number of attempts, anything below 1 still tries once
+    internal static int GetAttempts(IConfiguration configuration)
+    {
+        return Math.Max(1, ConfigurationHandler.GetInt(configuration, "startup_retries", DefaultAttempts));
+    }
+
+    // Retries transient failures with exponential backoff, everything else and the last failure are rethrown.
+    // Cancelling the token stops waiting for the next attempt with an OperationCanceledException.
+    internal static async Task RunAsync(string operation, Func<Task> action, int attempts,
+        CancellationToken cancellationToken = default)
+    {
+        var delay = InitialDelay;
+
+        for (var attempt = 1;; attempt++)
+            try
+            {
+                cancellationToken.ThrowIfCancellationRequested();
+                await action();
+                return;
+            }
+            catch (Exception ex) when (attempt < attempts && !cancellationToken.IsCancellationRequested &&
+                                       IsTransient(ex))
+            {
+                Log.Warning(ex, "{Operation} failed (attempt {Attempt} of {Attempts}), retrying in {Delay} s",
+                    operation, attempt, attempts, delay.TotalSeconds);
+
+                await Task.Delay(delay, cancellationToken);
+                delay = TimeSpan.FromTicks(Math.Min(delay.Ticks * 2, MaxDelay.Ticks));
+            }
+    }
+
+    // Network problems, timeouts, rate limits and server errors may go away on their own, a rejected token won't
+    private static bool IsTransient(Exception exception)
+    {
+        return exception switch
+        {
+            HttpException http => http.HttpCode is HttpStatusCode.TooManyRequests || (int)http.HttpCode >= 500,
+            HttpRequestException or TimeoutException or TaskCanceledException => true,
+            _ => false
+        };
+    }
+}
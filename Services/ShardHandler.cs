@@ -0,0 +1,82 @@
+using System.Collections.Concurrent;
+using Discord;
+using Discord.WebSocket;
+using Microsoft.Extensions.Configuration;
+using Serilog;
+
+namespace Red.Services;
+
+// ConsecutiveDisconnects counts disconnects since the shard last connected successfully
+public record ShardStatus(int Id, ConnectionState State, int Latency, int GuildCount, int ConsecutiveDisconnects);
+
+public class ShardHandler
+{
+    private const int DefaultLogIntervalSeconds = 300;
+    private const int DefaultReconnectAlertThreshold = 5;
+
+    private readonly DiscordShardedClient _client;
+    private readonly IConfiguration _configuration;
+    private readonly ConcurrentDictionary<int, int> _consecutiveDisconnects = new();
+
+    public ShardHandler(DiscordShardedClient client, IConfiguration configuration)
+    {
+        _client = client;
+        _configuration = configuration;
+    }
+
+    private int ReconnectAlertThreshold =>
+        ConfigurationHandler.GetInt(_configuration, "reconnect_alert_threshold", DefaultReconnectAlertThreshold);
+
+    // Discord.Net reconnects on its own with a backoff, we only keep track of how that is going
+    public void Initialize()
+    {
+        _client.ShardConnected += shard =>
+        {
+            if (_consecutiveDisconnects.TryRemove(shard.ShardId, out var count))
+                Log.Information("Shard {Id} reconnected to the gateway after {Count} disconnects", shard.ShardId, count);
+
+            return Task.CompletedTask;
+        };
+
+        _client.ShardDisconnected += (exception, shard) =>
+        {
+            var count = _consecutiveDisconnects.AddOrUpdate(shard.ShardId, 1, (_, x) => x + 1);
+
+            if (count == ReconnectAlertThreshold)
+                Log.Error(exception,
+                    "Shard {Id} lost the gateway connection {Count} times in a row without reconnecting, it may be stuck",
+                    shard.ShardId, count);
+            else
+                Log.Warning("Shard {Id} disconnected from the gateway ({Count} in a row): {Reason}", shard.ShardId,
+                    count, exception?.Message);
+
+            return Task.CompletedTask;
+        };
+    }
+
+    public IReadOnlyList<ShardStatus> GetStatuses()
+    {
+        return _client.Shards
+            .OrderBy(x => x.ShardId)
+            .Select(x => new ShardStatus(x.ShardId, x.ConnectionState, x.Latency, x.Guilds.Count,
+                _consecutiveDisconnects.GetValueOrDefault(x.ShardId)))
+            .ToList();
+    }
+
+    // Periodically logs every shard's health, "logging:shard_status_interval_secs" set to 0 disables it
+    public void StartLogging()
+    {
+        var seconds = ConfigurationHandler.GetInt(_configuration, "logging:shard_status_interval_secs",
+            DefaultLogIntervalSeconds);
+        if (seconds <= 0) return;
+
+        _ = Task.Run(async () =>
+        {
+            using var timer = new PeriodicTimer(TimeSpan.FromSeconds(seconds));
+            while (await timer.WaitForNextTickAsync())
+                foreach (var shard in GetStatuses())
+                    Log.Information("Shard {Id}: {State}, {Latency} ms, {GuildCount} guilds",
+                        shard.Id, shard.State, shard.Latency, shard.GuildCount);
+        });
+    }
+}
\ No newline at end of file